@@ -0,0 +1,293 @@
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use clap::{error::ErrorKind, Args, CommandFactory, Parser, Subcommand};
+use rust_of_life::state::{
+    builder::GameBuilder,
+    library::BUILTIN_PATTERNS,
+    rle::Rle,
+    rule::Rule,
+    rule_file::{builtin_rule, RuleFile},
+    runner::TICK_RATE_PER_SECOND,
+    soup::{parse_region, SoupParams, Symmetry, DEFAULT_DENSITY},
+    topology::Topology,
+    wolfram::{InitialRow, WolframRule},
+};
+use tracing::Level;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "rust-of-life",
+    version,
+    about = "Conway's game of life in the terminal.",
+    args_conflicts_with_subcommands = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Running without a subcommand is the same as `tui`.
+    #[command(flatten)]
+    pub tui: TuiArgs,
+
+    #[command(flatten)]
+    pub logging: LogArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the simulation in the terminal user interface (the default).
+    Tui(TuiArgs),
+    /// Run the simulation without a user interface and write the final board as RLE.
+    #[command(after_help = "Exit codes: 0 still evolving after the last generation, 1 failed to write the \
+                            output, 2 invalid arguments, 3 settled into a still life or oscillator, 4 died out.")]
+    Run(RunArgs),
+    /// Run many random soups on every core, recording what each one turns into.
+    #[command(after_help = "Soup n uses seed --seed + n. Results are appended to the results file as each soup \
+                            finishes, so running the same command again resumes an interrupted search.")]
+    Search(SearchArgs),
+    /// List the patterns built into the binary, usable with --pattern.
+    Library,
+}
+
+#[derive(Debug, Args)]
+pub struct LogArgs {
+    /// Directory the log files are written to.
+    #[arg(long, global = true, default_value = "./logs")]
+    pub log_dir: PathBuf,
+
+    /// Most verbose level that is logged: error, warn, info, debug or trace.
+    #[arg(long, global = true, default_value_t = Level::INFO)]
+    pub log_level: Level,
+}
+
+#[derive(Debug, Args)]
+pub struct TuiArgs {
+    #[command(flatten)]
+    pub game: GameArgs,
+
+    /// Generations per second while running.
+    #[arg(long, default_value_t = TICK_RATE_PER_SECOND, value_parser = parse_tick_rate)]
+    pub tick_rate: f64,
+
+    /// Start running the simulation immediately instead of paused.
+    #[arg(long)]
+    pub run: bool,
+
+    /// Pause once the board settles into a still life or oscillator.
+    #[arg(long)]
+    pub auto_pause: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub game: GameArgs,
+
+    /// Number of generations to run.
+    #[arg(long, short = 'n', default_value_t = 1000)]
+    pub generations: u64,
+
+    /// Stop early once the board dies out or settles into a still life or oscillator.
+    #[arg(long)]
+    pub until_stable: bool,
+
+    /// File the final board is written to, with a summary in its comments. Defaults to stdout.
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    #[command(flatten)]
+    pub game: GameArgs,
+
+    /// Number of soups to search.
+    #[arg(long, default_value_t = 1000)]
+    pub soups: u64,
+
+    /// Worker threads. Defaults to one per core.
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub threads: Option<u16>,
+
+    /// Generations a soup may run before it is recorded as unsettled.
+    #[arg(long, default_value_t = 10_000)]
+    pub max_generations: u64,
+
+    /// Lifespan from which a soup is flagged as a methuselah.
+    #[arg(long, default_value_t = 1000)]
+    pub methuselah: u64,
+
+    /// Tab separated file the results are appended to.
+    #[arg(long, default_value = "soup-search.tsv")]
+    pub results: PathBuf,
+}
+
+// Everything needed to set up a board, shared by every way of running it.
+#[derive(Debug, Args)]
+pub struct GameArgs {
+    /// Width of the board in cells.
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: u16,
+
+    /// Height of the board in cells.
+    #[arg(long, default_value_t = 64, value_parser = clap::value_parser!(u16).range(1..))]
+    pub height: u16,
+
+    /// Rule to run, e.g. B3/S23. Defaults to the pattern's rule, or Conway's Life.
+    #[arg(long)]
+    pub rule: Option<Rule>,
+
+    /// Golly .rule file with a @TABLE or @TREE, or name of a built-in one such as Wireworld, to run instead of a B/S rule.
+    #[arg(long, conflicts_with = "rule")]
+    pub rule_file: Option<String>,
+
+    /// One-dimensional rule whose generations scroll up the board: an elementary rule such as W30, or a totalistic one such as T1599,K3,R1.
+    #[arg(long, conflicts_with_all = ["rule", "rule_file"])]
+    pub wolfram: Option<WolframRule>,
+
+    /// First row of a one-dimensional rule: single, random (at the soup density) or the states of its cells, e.g. 1101.
+    #[arg(long, default_value_t = InitialRow::Single, requires = "wolfram")]
+    pub initial: InitialRow,
+
+    /// Behaviour of the board edges: bounded or torus.
+    #[arg(long, default_value_t = Topology::Bounded)]
+    pub topology: Topology,
+
+    /// RLE file, or name of a built-in pattern, to start from instead of a random soup.
+    #[arg(long)]
+    pub pattern: Option<String>,
+
+    /// Chance of each cell of the random soup starting alive, between 0 and 1.
+    #[arg(long, default_value_t = DEFAULT_DENSITY, value_parser = parse_density)]
+    pub density: f64,
+
+    /// Seed for the random number generator, making the run reproducible. A random seed is used when omitted.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Size of the random soup centered on the board, e.g. 16x16. Defaults to the whole board.
+    #[arg(long, value_parser = parse_region)]
+    pub soup_size: Option<(u32, u32)>,
+
+    /// Symmetry of the random soup: C1, C2, C4, D2, D4 or D8.
+    #[arg(long, default_value_t = Symmetry::C1)]
+    pub symmetry: Symmetry,
+}
+
+impl GameArgs {
+    pub fn soup_params(&self) -> SoupParams {
+        SoupParams {
+            density: self.density,
+            seed: None,
+            region: self.soup_size,
+            symmetry: self.symmetry,
+        }
+    }
+
+    // Sets up a game from the arguments: the pattern if one was given,
+    // otherwise a random soup.
+    pub fn builder(&self) -> Result<GameBuilder, String> {
+        let mut builder = GameBuilder::new()
+            .with_size(self.width as isize, self.height as isize)
+            .with_topology(self.topology)
+            // Resets use the soup options even when starting from a pattern.
+            .with_reset_soup(self.soup_params());
+        if let Some(seed) = self.seed {
+            builder = builder.with_seed(seed);
+        }
+
+        if let Some(rule_file) = self.rule_file.as_deref() {
+            builder = builder.with_rule_file(load_rule_file(rule_file)?);
+        }
+        if let Some(wolfram) = self.wolfram.clone() {
+            if let InitialRow::Cells(cells) = &self.initial {
+                if cells.iter().any(|state| *state >= wolfram.states) {
+                    return Err(format!("{wolfram} has no state above {}", wolfram.states - 1));
+                }
+            }
+            builder = builder.with_wolfram_rule(wolfram.with_initial_row(self.initial.clone()));
+        }
+
+        let pattern = self.pattern.as_deref().map(load_pattern).transpose()?;
+        // A pattern for a rule file names that file's rule, which may be a
+        // built-in one, and a one-dimensional pattern its rule.
+        let mut pattern_rule = None;
+        if let Some(rule) = pattern.as_ref().and_then(|rle| rle.rule.as_deref()) {
+            match (rule.parse::<Rule>(), builtin_rule(rule), rule.parse::<WolframRule>()) {
+                _ if self.rule_file.is_some() || self.rule.is_some() || self.wolfram.is_some() => {}
+                (Err(_), Some(builtin), _) => {
+                    builder = builder.with_rule_file(builtin.load().map_err(|error| error.to_string())?);
+                }
+                (Err(_), None, Ok(wolfram)) => builder = builder.with_wolfram_rule(wolfram),
+                (parsed, _, _) => pattern_rule = Some(parsed.map_err(|error| error.to_string())?),
+            }
+        }
+        if let Some(rule) = self.rule.or(pattern_rule) {
+            builder = builder.with_rule(rule);
+        }
+
+        Ok(match pattern {
+            Some(rle) => builder.with_pattern(rle.pattern),
+            None => builder.with_soup(self.soup_params()),
+        })
+    }
+}
+
+// Reports a problem with the arguments the way clap does and exits.
+pub fn exit_with_error(error: impl Display) -> ! {
+    Cli::command().error(ErrorKind::InvalidValue, error).exit()
+}
+
+// Loads an RLE file, or a built-in pattern by name when no such file exists.
+fn load_pattern(pattern: &str) -> Result<Rle, String> {
+    let path = Path::new(pattern);
+    if path.exists() {
+        let contents = std::fs::read_to_string(path).map_err(|error| format!("unable to read {pattern}: {error}"))?;
+        return Rle::parse(&contents).map_err(|error| format!("unable to parse {pattern}: {error}"));
+    }
+
+    let wanted = normalize_name(pattern);
+    BUILTIN_PATTERNS
+        .iter()
+        .find(|builtin| normalize_name(builtin.name) == wanted)
+        .ok_or_else(|| format!("no file or built-in pattern named `{pattern}`, see the library subcommand"))?
+        .load()
+        .map_err(|error| format!("unable to parse built-in pattern {pattern}: {error}"))
+}
+
+// Loads a Golly rule file, or a built-in rule by name when no such file exists.
+fn load_rule_file(rule_file: &str) -> Result<RuleFile, String> {
+    let path = Path::new(rule_file);
+    if path.exists() {
+        return RuleFile::load(path);
+    }
+
+    builtin_rule(rule_file)
+        .ok_or_else(|| format!("no file or built-in rule named `{rule_file}`"))?
+        .load()
+        .map_err(|error| format!("unable to parse built-in rule {rule_file}: {error}"))
+}
+
+fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn parse_density(input: &str) -> Result<f64, String> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|density| (0.0..=1.0).contains(density))
+        .ok_or_else(|| format!("density must be a number between 0 and 1, got `{input}`"))
+}
+
+fn parse_tick_rate(input: &str) -> Result<f64, String> {
+    input
+        .parse::<f64>()
+        .ok()
+        .filter(|tick_rate| *tick_rate > 0.0 && tick_rate.is_finite())
+        .ok_or_else(|| format!("tick rate must be a positive number, got `{input}`"))
+}
@@ -0,0 +1,146 @@
+use std::{fmt, io::Write, process::ExitCode};
+
+use rust_of_life::state::{
+    game::Game,
+    period::BoardCycle,
+};
+use tracing::info;
+
+use crate::cli::{self, RunArgs};
+
+// Exit codes of the `run` subcommand. Invalid arguments exit with 2, as for
+// every subcommand.
+const EXIT_FAILURE: u8 = 1;
+const EXIT_STABLE: u8 = 3;
+const EXIT_EXTINCT: u8 = 4;
+
+// How a headless run ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // Still changing after the last generation.
+    Evolving,
+    // Settled into a still life or oscillator, or a spaceship on a torus.
+    Stable(BoardCycle),
+    // No live cells left.
+    Extinct,
+}
+
+impl Outcome {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Outcome::Evolving => ExitCode::SUCCESS,
+            Outcome::Stable(_) => ExitCode::from(EXIT_STABLE),
+            Outcome::Extinct => ExitCode::from(EXIT_EXTINCT),
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Evolving => write!(f, "evolving"),
+            Outcome::Stable(cycle) if cycle.is_still_life() => write!(f, "still life"),
+            Outcome::Stable(cycle) if cycle.is_moving() => write!(f, "spaceship"),
+            Outcome::Stable(_) => write!(f, "oscillating"),
+            Outcome::Extinct => write!(f, "extinct"),
+        }
+    }
+}
+
+// What is reported about the board at the end of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub population: usize,
+    // Top left corner and size of the smallest rectangle holding every live
+    // cell, if there are any.
+    pub bounding_box: Option<((u32, u32), (u32, u32))>,
+    pub outcome: Outcome,
+}
+
+impl Summary {
+    fn of(game: &Game, outcome: Outcome) -> Self {
+        let alive: Vec<(u32, u32)> = game.alive_cells().collect();
+        let bounding_box = alive.iter().fold(None, |bounds: Option<((u32, u32), (u32, u32))>, &(x, y)| {
+            Some(match bounds {
+                None => ((x, y), (x, y)),
+                Some((min, max)) => ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y))),
+            })
+        });
+
+        Summary {
+            population: alive.len(),
+            bounding_box: bounding_box.map(|(min, max)| (min, (max.0 - min.0 + 1, max.1 - min.1 + 1))),
+            outcome,
+        }
+    }
+
+    // One `key value` line each, written as comments into the output RLE
+    // after the generation the run stopped at.
+    fn lines(&self) -> Vec<String> {
+        let bounding_box = match self.bounding_box {
+            Some(((x, y), (width, height))) => format!("{width}x{height} at {x},{y}"),
+            None => "none".to_string(),
+        };
+        let period = match self.outcome {
+            Outcome::Stable(cycle) if cycle.is_moving() => format!(
+                "{} from generation {}, moving {},{}",
+                cycle.period, cycle.onset, cycle.displacement.0, cycle.displacement.1
+            ),
+            Outcome::Stable(cycle) => format!("{} from generation {}", cycle.period, cycle.onset),
+            _ => "none".to_string(),
+        };
+
+        vec![
+            format!("outcome {}", self.outcome),
+            format!("population {}", self.population),
+            format!("bounding box {bounding_box}"),
+            format!("period {period}"),
+        ]
+    }
+}
+
+// Runs the game until the generation limit, or until it settles down when
+// asked to, returning how it ended up.
+pub fn run(game: &mut Game, generations: u64, until_stable: bool) -> Summary {
+    for _ in 0..generations {
+        if game.game_data().cycle.is_some() && until_stable {
+            break;
+        }
+        game.tick();
+    }
+
+    let summary = Summary::of(game, game.game_data().cycle.map_or(Outcome::Evolving, Outcome::Stable));
+    match summary.outcome {
+        Outcome::Stable(_) if summary.population == 0 => Summary {
+            outcome: Outcome::Extinct,
+            ..summary
+        },
+        _ => summary,
+    }
+}
+
+// The `run` subcommand: plays the game without a terminal interface and
+// writes the final board, with a summary in its comments, to stdout or a file.
+pub fn run_headless(args: RunArgs) -> ExitCode {
+    let mut game = args
+        .game
+        .builder()
+        .and_then(|builder| builder.build().map_err(|error| error.to_string()))
+        .unwrap_or_else(|error| cli::exit_with_error(error));
+
+    let summary = run(&mut game, args.generations, args.until_stable);
+    info!("Headless run finished: {}", summary.lines().join(", "));
+
+    let mut rle = game.to_rle();
+    rle.comments.extend(summary.lines());
+    let written = match args.output.as_ref() {
+        Some(path) => std::fs::write(path, rle.to_string()),
+        None => std::io::stdout().write_all(rle.to_string().as_bytes()),
+    };
+    if let Err(error) = written {
+        eprintln!("error: unable to write the final pattern: {error}");
+        return ExitCode::from(EXIT_FAILURE);
+    }
+
+    summary.outcome.exit_code()
+}
@@ -1,2 +1,3 @@
 pub mod state;
+#[cfg(feature = "tui")]
 pub mod view;
@@ -1,43 +1,75 @@
+mod cli;
+mod headless;
+mod soup_search;
 
-use cursive::{
-    Cursive, CursiveExt,
-};
+use std::process::ExitCode;
+
+use clap::Parser;
+use cli::{Cli, Command, TuiArgs};
+use cursive::{Cursive, CursiveExt};
 use rust_of_life::{
-    state::{
-        game::{Game, GameData},
-    },
-    view::ui::{ControlMessages, UserInterface},
+    state::{game::GameData, library::BUILTIN_PATTERNS, simulation_controls::ControlMessages},
+    view::ui::UserInterface,
 };
-use tracing::{info, Level};
+use tracing::info;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::FmtSubscriber;
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 2)]
-async fn main() {
-    // Create a rolling file appender that rotates logs every hour and writes to ./logs/my_log.log
-    let file_appender = RollingFileAppender::new(Rotation::MINUTELY, "./logs", "rust-of-life.log");
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // Create a rolling file appender that rotates logs every minute and writes to <log dir>/rust-of-life.log
+    let file_appender = RollingFileAppender::new(Rotation::MINUTELY, &cli.logging.log_dir, "rust-of-life.log");
     let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
 
     // Use the tracing_subscriber crate to consume the logs and pipe them to the file
     let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
+        .with_max_level(cli.logging.log_level)
         .with_writer(non_blocking)
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
+    match cli.command {
+        Some(Command::Tui(args)) => run_tui(args),
+        Some(Command::Run(args)) => return headless::run_headless(args),
+        Some(Command::Search(args)) => return soup_search::run_search(args),
+        Some(Command::Library) => {
+            for pattern in BUILTIN_PATTERNS {
+                println!("{:<12} {}", pattern.category, pattern.name);
+            }
+        }
+        None => run_tui(cli.tui),
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn run_tui(args: TuiArgs) {
     info!("Starting rust of life!");
     let (tx, rx) = tokio::sync::watch::channel::<GameData>(GameData::default());
     let (controls_tx, controls_rx) = tokio::sync::mpsc::channel::<ControlMessages>(100);
 
     let mut cursive_ref = Cursive::new();
-    let game = Game::randomized_board(64, 64)
-        .with_sender(tx)
-        .with_control_rx(controls_rx);
+    let runner = args
+        .game
+        .builder()
+        .and_then(|builder| {
+            builder
+                .with_running(args.run)
+                .with_auto_pause(args.auto_pause)
+                .with_tick_rate(args.tick_rate)
+                .with_sender(tx)
+                .with_control_rx(controls_rx)
+                .build_runner()
+                .map_err(|error| error.to_string())
+        })
+        .unwrap_or_else(|error| cli::exit_with_error(error));
 
     tokio::spawn(async move {
         tracing::info!("Starting game simulation.");
-        game.start().await;
+        runner.start().await;
     });
 
     let user_interface = UserInterface::init(rx, controls_tx, &mut cursive_ref);
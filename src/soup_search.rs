@@ -0,0 +1,392 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::Path,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+use rust_of_life::state::{
+    builder::GameBuilder,
+    census::{Census, ObjectKind},
+    history::HistoryLimit,
+};
+use tracing::info;
+
+use crate::cli::{self, SearchArgs};
+
+const RESULTS_HEADER: &str = "# rust-of-life soup search v1";
+const RESULTS_COLUMNS: &str = "soup\tseed\toutcome\tlifespan\tpopulation\tflags\tcensus";
+// How often progress is reported on stderr.
+const PROGRESS_EVERY: u64 = 100;
+
+// Objects turning up in almost every soup. Anything else found is flagged as
+// rare.
+const COMMON_OBJECTS: [&str; 14] = [
+    "xs4_33",
+    "xp2_7",
+    "xs6_696",
+    "xq4_153",
+    "xs7_2596",
+    "xs5_253",
+    "xs4_252",
+    "xs8_6996",
+    "xs6_356",
+    "xs7_25ac",
+    "xp2_318c",
+    "xp2_7e",
+    "xs6_25a4",
+    "xs8_69ic",
+];
+
+// What became of one soup.
+#[derive(Debug, Clone)]
+struct SoupResult {
+    soup: u64,
+    seed: u64,
+    // `stable`, `extinct` or `unsettled` if the soup was still going at the
+    // generation limit.
+    outcome: &'static str,
+    // Generations until the board started repeating, or the generation limit.
+    lifespan: u64,
+    population: usize,
+    census: Census,
+    methuselah: bool,
+    rare: Vec<String>,
+}
+
+impl SoupResult {
+    fn line(&self) -> String {
+        let mut flags: Vec<String> = Vec::new();
+        if self.methuselah {
+            flags.push("methuselah".to_string());
+        }
+        if !self.rare.is_empty() {
+            flags.push(format!("rare:{}", self.rare.join(",")));
+        }
+        let census: Vec<String> = self
+            .census
+            .entries
+            .iter()
+            .map(|entry| format!("{}*{}", entry.apgcode, entry.count))
+            .collect();
+
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.soup,
+            self.seed,
+            self.outcome,
+            self.lifespan,
+            self.population,
+            flags.join(" "),
+            census.join(" ")
+        )
+    }
+}
+
+// The settings a results file was started with. Resuming with different
+// ones would mix up soups that are not comparable.
+fn settings_line(args: &SearchArgs, seed: u64) -> String {
+    format!(
+        "# seed={seed} size={}x{} rule={} topology={} density={} soup={} symmetry={} max-generations={}",
+        args.game.width,
+        args.game.height,
+        args.game.rule.unwrap_or_default(),
+        args.game.topology,
+        args.game.density,
+        args.game
+            .soup_size
+            .map_or("board".to_string(), |(width, height)| format!("{width}x{height}")),
+        args.game.symmetry,
+        args.max_generations,
+    )
+}
+
+// Reads back a results file: the settings it was started with and the soups
+// already searched. A file with something in it but no settings line is an
+// error, as searching on would add soups no later run could tell apart.
+fn read_results(path: &Path) -> io::Result<Option<(String, HashSet<u64>)>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+
+    let mut settings = None;
+    let mut done = HashSet::new();
+    // A last line without a newline was cut short by an interrupted search
+    // and doesn't count.
+    for line in contents.split_inclusive('\n').filter_map(|line| line.strip_suffix('\n')) {
+        if line.starts_with("# seed=") {
+            settings = Some(line.to_string());
+        } else if let Some(soup) = line.split('\t').next().and_then(|soup| soup.parse::<u64>().ok()) {
+            done.insert(soup);
+        }
+    }
+
+    match settings {
+        Some(settings) => Ok(Some((settings, done))),
+        None if contents.is_empty() => Ok(None),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "it has no `# seed=` settings line, so it isn't the results of a soup search",
+        )),
+    }
+}
+
+fn ends_with_newline(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    let mut last = [0; 1];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+
+    Ok(last[0] == b'\n')
+}
+
+fn seed_in(settings: &str) -> Option<u64> {
+    settings
+        .split_whitespace()
+        .find_map(|setting| setting.strip_prefix("seed="))
+        .and_then(|seed| seed.parse().ok())
+}
+
+// The seed to search from and the soups already searched, carrying on from
+// what `read_results` found. Gives back the settings line of the file if it
+// was started with other settings.
+fn resume(args: &SearchArgs, existing: Option<(String, HashSet<u64>)>) -> Result<(u64, HashSet<u64>), String> {
+    match existing {
+        Some((settings, done)) => {
+            let seed = seed_in(&settings).unwrap_or_default();
+            if args.game.seed.is_some_and(|requested| requested != seed) || settings != settings_line(args, seed) {
+                return Err(settings);
+            }
+            Ok((seed, done))
+        }
+        None => Ok((args.game.seed.unwrap_or_else(rand::random), HashSet::new())),
+    }
+}
+
+// Runs soup number `soup` until it settles or hits the generation limit.
+fn search_soup(args: &SearchArgs, soup: u64, seed: u64) -> Result<SoupResult, String> {
+    let mut builder = GameBuilder::new()
+        .with_size(args.game.width as isize, args.game.height as isize)
+        .with_topology(args.game.topology)
+        .with_seed(seed)
+        .with_soup(args.game.soup_params())
+        .with_history_limit(HistoryLimit {
+            max_generations: 0,
+            max_bytes: None,
+        });
+    if let Some(rule) = args.game.rule {
+        builder = builder.with_rule(rule);
+    }
+    let mut game = builder.build().map_err(|error| error.to_string())?;
+
+    while game.game_data().cycle.is_none() && game.generation() < args.max_generations {
+        game.tick();
+    }
+
+    let cycle = game.game_data().cycle;
+    let population = game.population();
+    let census = Census::of_board(&game.game_data().cells, game.rule());
+    let lifespan = cycle.map_or(game.generation(), |cycle| cycle.onset);
+    let rare = census
+        .entries
+        .iter()
+        .filter(|entry| entry.kind != ObjectKind::Unsettled && !COMMON_OBJECTS.contains(&entry.apgcode.as_str()))
+        .map(|entry| entry.apgcode.clone())
+        .collect();
+
+    Ok(SoupResult {
+        soup,
+        seed,
+        outcome: match cycle {
+            None => "unsettled",
+            Some(_) if population == 0 => "extinct",
+            Some(_) => "stable",
+        },
+        lifespan,
+        population,
+        census,
+        methuselah: lifespan >= args.methuselah,
+        rare,
+    })
+}
+
+// The `search` subcommand: runs many random soups on every core and appends
+// what became of each to a results file, skipping soups already in it.
+pub fn run_search(args: SearchArgs) -> ExitCode {
+    if args.game.pattern.is_some() {
+        cli::exit_with_error("--pattern can't be used when searching soups");
+    }
+    if args.game.rule_file.is_some() {
+        cli::exit_with_error("--rule-file can't be used when searching soups, the census needs a B/S rule");
+    }
+    if args.game.wolfram.is_some() {
+        cli::exit_with_error("--wolfram can't be used when searching soups, the census needs a B/S rule");
+    }
+
+    let existing = read_results(&args.results).unwrap_or_else(|error| {
+        cli::exit_with_error(format!("unable to read {}: {error}", args.results.display()))
+    });
+    let (base_seed, done) = resume(&args, existing).unwrap_or_else(|settings| {
+        cli::exit_with_error(format!(
+            "{} was started with other settings: {settings}",
+            args.results.display()
+        ))
+    });
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args.results)
+        .and_then(|mut file| {
+            // Finish off a line cut short by an interrupted search.
+            if !ends_with_newline(&args.results)? {
+                writeln!(file)?;
+            }
+            if done.is_empty() && file.metadata()?.len() == 0 {
+                writeln!(file, "{RESULTS_HEADER}")?;
+                writeln!(file, "{}", settings_line(&args, base_seed))?;
+                writeln!(file, "{RESULTS_COLUMNS}")?;
+            }
+            Ok(file)
+        });
+    let results = match file {
+        Ok(file) => Mutex::new(file),
+        Err(error) => {
+            eprintln!("error: unable to open {}: {error}", args.results.display());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let threads = args.threads.map_or_else(
+        || thread::available_parallelism().map_or(1, |threads| threads.get()),
+        |threads| threads as usize,
+    );
+    let remaining = (0..args.soups).filter(|soup| !done.contains(soup)).count() as u64;
+    info!("Searching {remaining} soups from seed {base_seed} on {threads} threads.");
+    eprintln!(
+        "Searching {remaining} of {} soups from seed {base_seed} on {threads} threads.",
+        args.soups
+    );
+
+    let next_soup = AtomicU64::new(0);
+    let searched = AtomicU64::new(0);
+    let totals: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+    let failure: Mutex<Option<String>> = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let soup = next_soup.fetch_add(1, Ordering::Relaxed);
+                if soup >= args.soups || failure.lock().is_ok_and(|failure| failure.is_some()) {
+                    break;
+                }
+                if done.contains(&soup) {
+                    continue;
+                }
+
+                let written = search_soup(&args, soup, base_seed.wrapping_add(soup)).and_then(|result| {
+                    if result.methuselah || !result.rare.is_empty() {
+                        info!("Soup {soup} flagged: {}", result.line());
+                    }
+                    if let Ok(mut totals) = totals.lock() {
+                        if result.methuselah {
+                            *totals.entry("methuselahs".to_string()).or_default() += 1;
+                        }
+                        for code in result.rare.iter() {
+                            *totals.entry(code.clone()).or_default() += 1;
+                        }
+                    }
+                    let mut results = results.lock().map_err(|error| error.to_string())?;
+                    writeln!(results, "{}", result.line()).map_err(|error| error.to_string())
+                });
+                if let Err(error) = written {
+                    if let Ok(mut failure) = failure.lock() {
+                        failure.get_or_insert(error);
+                    }
+                    break;
+                }
+
+                let searched = searched.fetch_add(1, Ordering::Relaxed) + 1;
+                if searched.is_multiple_of(PROGRESS_EVERY) {
+                    eprintln!("{searched}/{remaining} soups searched");
+                }
+            });
+        }
+    });
+
+    if let Some(error) = failure.into_inner().ok().flatten() {
+        eprintln!("error: soup search stopped: {error}");
+        return ExitCode::FAILURE;
+    }
+
+    println!("Searched {} soups, results in {}.", searched.into_inner(), args.results.display());
+    for (flag, count) in totals.into_inner().unwrap_or_default() {
+        println!("{count:>6}  {flag}");
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::{Cli, Command};
+
+    fn search_args(args: &[&str]) -> SearchArgs {
+        match Cli::parse_from(["rust-of-life", "search"].iter().chain(args)).command {
+            Some(Command::Search(args)) => args,
+            command => panic!("expected the search subcommand, got {command:?}"),
+        }
+    }
+
+    #[test]
+    fn resumes_only_with_the_same_settings() {
+        let args = search_args(&["--seed", "7", "--width", "16"]);
+        let settings = settings_line(&args, 7);
+        // Soup 3 was being written when the search was interrupted.
+        let soups = "0\t7\tstable\t12\t4\t\txs4_33*1\n2\t9\textinct\t30\t0\t\t\n3\t10\tsta";
+        let path = std::env::temp_dir().join(format!("rust-of-life-soup-search-test-{}.tsv", std::process::id()));
+        std::fs::write(&path, format!("{RESULTS_HEADER}\n{settings}\n{RESULTS_COLUMNS}\n{soups}")).unwrap();
+        let existing = read_results(&path);
+        let finished = ends_with_newline(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let existing = existing.unwrap();
+        assert!(!finished.unwrap());
+        assert_eq!(existing, Some((settings.clone(), HashSet::from([0, 2]))));
+        assert_eq!(resume(&args, existing.clone()), Ok((7, HashSet::from([0, 2]))));
+        // The seed may be left out, it is taken from the file.
+        let unseeded = search_args(&["--width", "16"]);
+        assert_eq!(resume(&unseeded, existing.clone()).map(|(seed, _)| seed), Ok(7));
+        let reseeded = search_args(&["--seed", "8", "--width", "16"]);
+        assert_eq!(resume(&reseeded, existing.clone()), Err(settings.clone()));
+        let resized = search_args(&["--seed", "7", "--width", "32"]);
+        assert_eq!(resume(&resized, existing), Err(settings));
+        assert_eq!(read_results(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_results_without_settings() {
+        let path = std::env::temp_dir().join(format!("rust-of-life-soup-search-bare-{}.tsv", std::process::id()));
+        std::fs::write(&path, "0\t7\tstable\t12\t4\t\txs4_33*1\n").unwrap();
+        let bare = read_results(&path);
+        std::fs::write(&path, "").unwrap();
+        let empty = read_results(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bare.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(empty.unwrap(), None);
+    }
+}
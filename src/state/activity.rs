@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use super::history::Delta;
+
+pub const DEFAULT_ACTIVITY_WINDOW: usize = 64;
+
+// How many times each cell changed state recently, indexed `[x][y]` like the
+// board.
+pub type Heatmap = Vec<Vec<u32>>;
+
+pub fn blank_heatmap(size_x: isize, size_y: isize) -> Heatmap {
+    vec![vec![0; size_y.max(0) as usize]; size_x.max(0) as usize]
+}
+
+// Keeps a heatmap counting the state changes of the last `window`
+// generations, forgetting the oldest generation as each new one comes in.
+#[derive(Debug, Clone)]
+pub struct ActivityTracker {
+    window: usize,
+    recent: VecDeque<Vec<(u32, u32)>>,
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        ActivityTracker::new(DEFAULT_ACTIVITY_WINDOW)
+    }
+}
+
+impl ActivityTracker {
+    pub fn new(window: usize) -> Self {
+        ActivityTracker {
+            window: window.max(1),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    pub fn record(&mut self, delta: &Delta, heatmap: &mut Heatmap) {
+        let changed: Vec<(u32, u32)> = delta.changes().iter().map(|change| change.pos).collect();
+        for (x, y) in changed.iter() {
+            adjust(heatmap, (*x, *y), |count| count.saturating_add(1));
+        }
+        self.recent.push_back(changed);
+
+        while self.recent.len() > self.window {
+            for (x, y) in self.recent.pop_front().unwrap_or_default() {
+                adjust(heatmap, (x, y), |count| count.saturating_sub(1));
+            }
+        }
+    }
+
+    pub fn reset(&mut self, heatmap: &mut Heatmap) {
+        self.recent.clear();
+        heatmap.iter_mut().flatten().for_each(|count| *count = 0);
+    }
+}
+
+fn adjust(heatmap: &mut Heatmap, (x, y): (u32, u32), change: impl Fn(u32) -> u32) {
+    if let Some(count) = heatmap.get_mut(x as usize).and_then(|column| column.get_mut(y as usize)) {
+        *count = change(*count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::Cell;
+    use crate::state::game::Board;
+
+    fn board_with(alive: &[(u32, u32)]) -> Board {
+        (0..3)
+            .map(|x| {
+                (0..3)
+                    .map(|y| {
+                        let mut cell = Cell::new(x, y);
+                        if alive.contains(&(x, y)) {
+                            cell.reanimate();
+                        }
+                        Some(cell)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn forgets_changes_outside_the_window() {
+        let empty = board_with(&[]);
+        let one = board_with(&[(1, 1)]);
+        let mut heatmap = blank_heatmap(3, 3);
+        let mut tracker = ActivityTracker::new(2);
+
+        tracker.record(&Delta::between(&empty, &one), &mut heatmap);
+        tracker.record(&Delta::between(&one, &empty), &mut heatmap);
+        assert_eq!(heatmap[1][1], 2);
+        tracker.record(&Delta::between(&empty, &empty), &mut heatmap);
+        assert_eq!(heatmap[1][1], 1);
+
+        tracker.reset(&mut heatmap);
+        assert!(heatmap.iter().flatten().all(|count| *count == 0));
+    }
+}
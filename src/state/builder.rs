@@ -0,0 +1,387 @@
+use std::fmt;
+
+#[cfg(feature = "runner")]
+use tokio::sync::{mpsc::Receiver, watch::Sender};
+
+use super::game::{Game, GameData};
+use super::history::HistoryLimit;
+use super::neighborhood::Shape;
+use super::pattern::Pattern;
+use super::rule::Rule;
+use super::rule_file::RuleFile;
+use super::wolfram::WolframRule;
+#[cfg(feature = "runner")]
+use super::runner::{GameRunner, TICK_RATE_PER_SECOND};
+#[cfg(feature = "runner")]
+use super::simulation_controls::ControlMessages;
+use super::soup::SoupParams;
+use super::topology::Topology;
+
+pub const DEFAULT_BOARD_SIZE: isize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    // Both sides of the board must be at least one cell and positions must
+    // fit in a `u32`.
+    InvalidSize { size_x: isize, size_y: isize },
+    InvalidDensity(f64),
+    InvalidTickRate(f64),
+    // The board of the `GameData` to start from does not have the size the
+    // game was given, or its columns differ in length.
+    MismatchedGameData { size: (isize, isize), found: (usize, usize) },
+    InvalidGameDataRule(String),
+    // A hex grid only wraps onto itself vertically with an even number of
+    // rows, as odd rows are shifted.
+    OddHexagonalTorus { size_y: isize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::InvalidSize { size_x, size_y } => {
+                write!(f, "invalid board size {size_x}x{size_y}, both sides must be at least 1")
+            }
+            BuildError::InvalidDensity(density) => write!(f, "invalid soup density {density}, expected 0 to 1"),
+            BuildError::InvalidTickRate(tick_rate) => {
+                write!(f, "invalid tick rate {tick_rate}, expected a positive number")
+            }
+            BuildError::MismatchedGameData { size, found } => write!(
+                f,
+                "game data has a {}x{} board, expected {}x{}",
+                found.0, found.1, size.0, size.1
+            ),
+            BuildError::InvalidGameDataRule(rule) => write!(f, "game data has an invalid rule `{rule}`"),
+            BuildError::OddHexagonalTorus { size_y } => {
+                write!(f, "a hexagonal rule on a torus needs an even height, not {size_y}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+// What the board holds when the game starts.
+#[derive(Debug, Clone, Default)]
+pub enum InitialBoard {
+    #[default]
+    Empty,
+    Soup(SoupParams),
+    // Centered on the board and cropped to it.
+    Pattern(Pattern),
+    // Picks up where an earlier game left off.
+    GameData(Box<GameData>),
+}
+
+// Sets up a `Game`, or a `GameRunner` playing one, checking the settings
+// before anything is built.
+#[derive(Debug)]
+pub struct GameBuilder {
+    size: (isize, isize),
+    initial: InitialBoard,
+    rule: Option<Rule>,
+    rule_file: Option<RuleFile>,
+    wolfram: Option<WolframRule>,
+    topology: Topology,
+    seed: Option<u64>,
+    reset_soup: Option<SoupParams>,
+    history_limit: HistoryLimit,
+    running: bool,
+    auto_pause: bool,
+    #[cfg(feature = "runner")]
+    tick_rate: f64,
+    #[cfg(feature = "runner")]
+    sender: Option<Sender<GameData>>,
+    #[cfg(feature = "runner")]
+    control_rx: Option<Receiver<ControlMessages>>,
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        GameBuilder {
+            size: (DEFAULT_BOARD_SIZE, DEFAULT_BOARD_SIZE),
+            initial: InitialBoard::default(),
+            rule: None,
+            rule_file: None,
+            wolfram: None,
+            topology: Topology::default(),
+            seed: None,
+            reset_soup: None,
+            history_limit: HistoryLimit::default(),
+            running: false,
+            auto_pause: false,
+            #[cfg(feature = "runner")]
+            tick_rate: TICK_RATE_PER_SECOND,
+            #[cfg(feature = "runner")]
+            sender: None,
+            #[cfg(feature = "runner")]
+            control_rx: None,
+        }
+    }
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        GameBuilder::default()
+    }
+
+    pub fn with_size(mut self, size_x: isize, size_y: isize) -> Self {
+        self.size = (size_x, size_y);
+
+        self
+    }
+
+    pub fn with_initial_board(mut self, initial: InitialBoard) -> Self {
+        self.initial = initial;
+
+        self
+    }
+
+    pub fn with_soup(self, soup_params: SoupParams) -> Self {
+        self.with_initial_board(InitialBoard::Soup(soup_params))
+    }
+
+    pub fn with_pattern(self, pattern: Pattern) -> Self {
+        self.with_initial_board(InitialBoard::Pattern(pattern))
+    }
+
+    // Continues from `game_data`, taking the size of its board. Its rule is
+    // used unless another one is set.
+    pub fn with_game_data(mut self, game_data: GameData) -> Self {
+        self.size = (
+            game_data.cells.len() as isize,
+            game_data.cells.iter().map(|column| column.len()).max().unwrap_or(0) as isize,
+        );
+        self.with_initial_board(InitialBoard::GameData(Box::new(game_data)))
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = Some(rule);
+
+        self
+    }
+
+    // Runs a Golly rule file, taking the place of any rule.
+    pub fn with_rule_file(mut self, rule_file: RuleFile) -> Self {
+        self.rule_file = Some(rule_file);
+
+        self
+    }
+
+    // Runs a one-dimensional rule, taking the place of any rule. A soup
+    // fills the bottom row with the rule's initial row instead.
+    pub fn with_wolfram_rule(mut self, wolfram: WolframRule) -> Self {
+        self.wolfram = Some(wolfram);
+
+        self
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+
+        self
+    }
+
+    // Seed for the random number generator, random when not set.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+
+        self
+    }
+
+    // Soup the board is filled with when the game is reset. Defaults to the
+    // initial soup, if there is one.
+    pub fn with_reset_soup(mut self, soup_params: SoupParams) -> Self {
+        self.reset_soup = Some(soup_params);
+
+        self
+    }
+
+    pub fn with_history_limit(mut self, history_limit: HistoryLimit) -> Self {
+        self.history_limit = history_limit;
+
+        self
+    }
+
+    pub fn with_running(mut self, running: bool) -> Self {
+        self.running = running;
+
+        self
+    }
+
+    // Stop running once the board starts repeating itself.
+    pub fn with_auto_pause(mut self, auto_pause: bool) -> Self {
+        self.auto_pause = auto_pause;
+
+        self
+    }
+
+    // Generations per second while a runner plays the game.
+    #[cfg(feature = "runner")]
+    pub fn with_tick_rate(mut self, tick_rate: f64) -> Self {
+        self.tick_rate = tick_rate;
+
+        self
+    }
+
+    #[cfg(feature = "runner")]
+    pub fn with_sender(mut self, tx: Sender<GameData>) -> Self {
+        self.sender = Some(tx);
+
+        self
+    }
+
+    #[cfg(feature = "runner")]
+    pub fn with_control_rx(mut self, rx: Receiver<ControlMessages>) -> Self {
+        self.control_rx = Some(rx);
+
+        self
+    }
+
+    pub fn build(self) -> Result<Game, BuildError> {
+        let (size_x, size_y) = self.size;
+        if size_x < 1 || size_y < 1 || size_x > u32::MAX as isize || size_y > u32::MAX as isize {
+            return Err(BuildError::InvalidSize { size_x, size_y });
+        }
+
+        let mut game = Game::empty_board(size_x, size_y)
+            .with_history_limit(self.history_limit)
+            .with_topology(self.topology);
+        if let Some(seed) = self.seed {
+            game = game.with_seed(seed);
+        }
+
+        let (rule_file, wolfram) = (self.rule_file, self.wolfram);
+        let with_rules = |game: Game, rule: Rule| match (rule_file.clone(), wolfram.clone()) {
+            (Some(rule_file), _) => game.with_rule(rule).with_rule_file(rule_file),
+            (None, Some(wolfram)) => game.with_rule(rule).with_wolfram_rule(wolfram),
+            (None, None) => game.with_rule(rule),
+        };
+        game = match self.initial {
+            InitialBoard::Empty => with_rules(game, self.rule.unwrap_or_default()),
+            InitialBoard::Soup(soup_params) => {
+                check_density(&soup_params)?;
+                with_rules(game, self.rule.unwrap_or_default()).with_soup(soup_params)
+            }
+            InitialBoard::Pattern(pattern) => with_rules(game, self.rule.unwrap_or_default()).with_pattern(&pattern),
+            InitialBoard::GameData(mut game_data) => {
+                let found = (
+                    game_data.cells.len(),
+                    game_data.cells.iter().map(|column| column.len()).min().unwrap_or(0),
+                );
+                let ragged = game_data.cells.iter().any(|column| column.len() != found.1);
+                if ragged || found != (size_x as usize, size_y as usize) {
+                    return Err(BuildError::MismatchedGameData { size: self.size, found });
+                }
+                let rule = match self.rule {
+                    Some(rule) => rule,
+                    None if game_data.rule.is_empty() || rule_file.is_some() || wolfram.is_some() => Rule::default(),
+                    None => game_data
+                        .rule
+                        .parse()
+                        .map_err(|_| BuildError::InvalidGameDataRule(game_data.rule.clone()))?,
+                };
+                if let Some(seed) = self.seed {
+                    game_data.seed = seed;
+                }
+                // A board of a different size can't be compared against.
+                if game_data.previous_generation.len() != found.0 {
+                    game_data.previous_generation = game_data.cells.clone();
+                }
+                with_rules(game, rule).with_game_data(*game_data)
+            }
+        };
+
+        if let Some(soup_params) = self.reset_soup {
+            check_density(&soup_params)?;
+            game = game.with_reset_soup(soup_params);
+        }
+
+        let hexagonal = game.game_data().shape == Shape::Hexagonal;
+        if hexagonal && self.topology == Topology::Torus && size_y % 2 == 1 {
+            return Err(BuildError::OddHexagonalTorus { size_y });
+        }
+
+        Ok(game.with_running(self.running).with_auto_pause(self.auto_pause))
+    }
+
+    // Builds the game along with a runner to play it.
+    #[cfg(feature = "runner")]
+    pub fn build_runner(mut self) -> Result<GameRunner, BuildError> {
+        let tick_rate = self.tick_rate;
+        if !(tick_rate > 0.0 && tick_rate.is_finite()) {
+            return Err(BuildError::InvalidTickRate(tick_rate));
+        }
+        let sender = self.sender.clone();
+        let control_rx = self.control_rx.take();
+        let mut runner = GameRunner::new(self.build()?).with_tick_rate(tick_rate);
+        if let Some(sender) = sender {
+            runner = runner.with_sender(sender);
+        }
+        if let Some(control_rx) = control_rx {
+            runner = runner.with_control_rx(control_rx);
+        }
+
+        Ok(runner)
+    }
+}
+
+fn check_density(soup_params: &SoupParams) -> Result<(), BuildError> {
+    if (0.0..=1.0).contains(&soup_params.density) {
+        Ok(())
+    } else {
+        Err(BuildError::InvalidDensity(soup_params.density))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::CellState;
+
+    #[test]
+    fn rejects_invalid_settings() {
+        assert_eq!(
+            GameBuilder::new().with_size(-1, 10).build().err(),
+            Some(BuildError::InvalidSize { size_x: -1, size_y: 10 })
+        );
+        let soup = SoupParams {
+            density: 1.5,
+            ..SoupParams::default()
+        };
+        assert_eq!(
+            GameBuilder::new().with_soup(soup).build().err(),
+            Some(BuildError::InvalidDensity(1.5))
+        );
+        let hexagonal = GameBuilder::new()
+            .with_rule("B2/S34H".parse().unwrap())
+            .with_topology(Topology::Torus);
+        assert_eq!(
+            hexagonal.with_size(8, 7).build().err(),
+            Some(BuildError::OddHexagonalTorus { size_y: 7 })
+        );
+    }
+
+    #[test]
+    fn continues_from_game_data() {
+        let mut game = GameBuilder::new()
+            .with_size(8, 6)
+            .with_rule("B36/S23".parse().unwrap())
+            .with_seed(7)
+            .build()
+            .unwrap();
+        game.set_cell((2, 3), CellState::Alive);
+
+        let restored = GameBuilder::new().with_game_data(game.game_data().clone()).build().unwrap();
+        assert_eq!((restored.size_x, restored.size_y), (8, 6));
+        assert_eq!(restored.rule().to_string(), "B36/S23");
+        assert_eq!(restored.game_data().seed, 7);
+        assert!(restored.alive_cells().eq([(2, 3)]));
+
+        let mut ragged = game.game_data().clone();
+        ragged.cells[0].pop();
+        assert_eq!(
+            GameBuilder::new().with_game_data(ragged).build().err(),
+            Some(BuildError::MismatchedGameData { size: (8, 6), found: (8, 5) })
+        );
+    }
+}
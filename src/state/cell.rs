@@ -1,9 +1,31 @@
-// An enum to indicate the cell state. The inner value
-// of the enum is the number of cells alive around the cell.
-#[derive(Debug, Clone)]
+// An enum to indicate the cell state. Under a Generations rule a cell that
+// dies first passes through `Dying` states, counting the generations since
+// it was alive, during which it can't be born again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CellState {
     Alive,
     Dead,
+    Dying(u8),
+}
+
+impl CellState {
+    // The state's number in multi-state formats: 0 is dead, 1 alive and 2
+    // onwards the dying states in order.
+    pub fn index(&self) -> u8 {
+        match self {
+            CellState::Dead => 0,
+            CellState::Alive => 1,
+            CellState::Dying(age) => age.saturating_add(1),
+        }
+    }
+
+    pub fn from_index(index: u8) -> Self {
+        match index {
+            0 => CellState::Dead,
+            1 => CellState::Alive,
+            index => CellState::Dying(index - 1),
+        }
+    }
 }
 
 // Represent a single cell on the board.
@@ -0,0 +1,375 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::cell::CellState;
+use super::game::Board;
+use super::rule::Rule;
+use super::sparse::{detect_period_of, SparseLife};
+
+// How long an object is run on its own to find out what it is.
+const CLASSIFY_GENERATIONS: u32 = 512;
+// Digits of the extended Wechsler format. The first 32 encode a column of
+// five cells; all of them count zeros after a `y`.
+const WECHSLER_DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const STRIP_HEIGHT: i64 = 5;
+// How long parts of an object have to evolve apart as they do together to be
+// counted separately.
+const INDEPENDENCE_GENERATIONS: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    // Still changing, or not repeating within the time it was given.
+    Unsettled,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectKind::StillLife => write!(f, "still life"),
+            ObjectKind::Oscillator => write!(f, "oscillator"),
+            ObjectKind::Spaceship => write!(f, "spaceship"),
+            ObjectKind::Unsettled => write!(f, "unsettled"),
+        }
+    }
+}
+
+// One kind of object found on the board and how many there were.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CensusEntry {
+    // Canonical code as used by apgsearch and Catagolue, e.g. `xs4_33` for
+    // a block. Unsettled objects get `zz_UNSETTLED`.
+    pub apgcode: String,
+    pub kind: ObjectKind,
+    pub period: Option<u32>,
+    pub population: usize,
+    pub count: usize,
+}
+
+// The objects on a board, most common first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+}
+
+impl Census {
+    pub fn of_board(board: &Board, rule: Rule) -> Self {
+        Census::of_cells(
+            board
+                .iter()
+                .flatten()
+                .flatten()
+                .filter(|cell| cell.state == CellState::Alive)
+                .map(|cell| (cell.pos.0 as i64, cell.pos.1 as i64)),
+            rule,
+        )
+    }
+
+    pub fn of_cells(cells: impl IntoIterator<Item = (i64, i64)>, rule: Rule) -> Self {
+        let mut entries: HashMap<String, CensusEntry> = HashMap::new();
+        for island in islands(&cells.into_iter().collect(), rule) {
+            let (apgcode, kind, period) = classify(&island, rule);
+            entries
+                .entry(apgcode.clone())
+                .or_insert(CensusEntry {
+                    apgcode,
+                    kind,
+                    period,
+                    population: island.len(),
+                    count: 0,
+                })
+                .count += 1;
+        }
+
+        let mut entries: Vec<CensusEntry> = entries.into_values().collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.apgcode.cmp(&b.apgcode)));
+        Census { entries }
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("apgcode,kind,period,population,count\n");
+        for entry in self.entries.iter() {
+            let period = entry.period.map(|period| period.to_string()).unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                entry.apgcode, entry.kind, period, entry.population, entry.count
+            ));
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let objects: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let period = entry.period.map_or("null".to_string(), |period| period.to_string());
+                format!(
+                    "  {{\"apgcode\": \"{}\", \"kind\": \"{}\", \"period\": {}, \"population\": {}, \"count\": {}}}",
+                    entry.apgcode, entry.kind, period, entry.population, entry.count
+                )
+            })
+            .collect();
+
+        if objects.is_empty() {
+            "[]\n".to_string()
+        } else {
+            format!("[\n{}\n]\n", objects.join(",\n"))
+        }
+    }
+}
+
+// Splits live cells into separate objects. Cells up to two apart can affect
+// the same cell, so they start out as one object; it is split back into the
+// groups of touching cells it is made of if those evolve the same apart as
+// together, as with a pair of blocks one cell apart.
+pub fn islands(cells: &HashSet<(i64, i64)>, rule: Rule) -> Vec<Vec<(i64, i64)>> {
+    let mut islands = Vec::new();
+    for cluster in components(cells, 2) {
+        let parts = components(&cluster.iter().copied().collect(), 1);
+        if parts.len() > 1 && evolve_independently(&cluster, &parts, rule) {
+            islands.extend(parts);
+        } else {
+            islands.push(cluster);
+        }
+    }
+
+    islands
+}
+
+// Groups cells that are connected through steps of at most `reach` cells.
+fn components(cells: &HashSet<(i64, i64)>, reach: i64) -> Vec<Vec<(i64, i64)>> {
+    let mut unvisited = cells.clone();
+    let mut components = Vec::new();
+    let mut sorted: Vec<(i64, i64)> = cells.iter().copied().collect();
+    sorted.sort_unstable();
+
+    for start in sorted {
+        if !unvisited.remove(&start) {
+            continue;
+        }
+        let mut component = vec![start];
+        let mut next = 0;
+        while let Some(&(x, y)) = component.get(next) {
+            next += 1;
+            for delta_x in -reach..=reach {
+                for delta_y in -reach..=reach {
+                    if unvisited.remove(&(x + delta_x, y + delta_y)) {
+                        component.push((x + delta_x, y + delta_y));
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    components
+}
+
+fn evolve_independently(cluster: &[(i64, i64)], parts: &[Vec<(i64, i64)>], rule: Rule) -> bool {
+    let mut together = SparseLife::from_cells(cluster.iter().copied()).with_rule(rule);
+    let mut apart: Vec<SparseLife> = parts
+        .iter()
+        .map(|part| SparseLife::from_cells(part.iter().copied()).with_rule(rule))
+        .collect();
+    for _ in 0..INDEPENDENCE_GENERATIONS {
+        together.step();
+        apart.iter_mut().for_each(SparseLife::step);
+        let union: HashSet<(i64, i64)> = apart.iter().flat_map(|part| part.cells().copied()).collect();
+        let joint: HashSet<(i64, i64)> = together.cells().copied().collect();
+        if union != joint {
+            return false;
+        }
+    }
+
+    true
+}
+
+// Runs an object on its own to find its apgcode, kind and period.
+fn classify(island: &[(i64, i64)], rule: Rule) -> (String, ObjectKind, Option<u32>) {
+    // The trail of dying cells a Generations object leaves behind, and
+    // neighborhoods wider than the nearest cells, aren't followed when
+    // running it on its own.
+    if rule.is_generations() || !rule.neighborhood.is_nearest() {
+        return ("zz_UNSETTLED".to_string(), ObjectKind::Unsettled, None);
+    }
+    let life = SparseLife::from_cells(island.iter().copied()).with_rule(rule);
+    let periodicity = match detect_period_of(life.clone(), CLASSIFY_GENERATIONS) {
+        Some(periodicity) if periodicity.onset == 0 => periodicity,
+        _ => return ("zz_UNSETTLED".to_string(), ObjectKind::Unsettled, None),
+    };
+
+    let (prefix, kind) = match periodicity {
+        periodicity if periodicity.displacement != (0, 0) => ("xq", ObjectKind::Spaceship),
+        periodicity if periodicity.period == 1 => ("xs", ObjectKind::StillLife),
+        _ => ("xp", ObjectKind::Oscillator),
+    };
+    let size = match kind {
+        ObjectKind::StillLife => island.len() as u32,
+        _ => periodicity.period,
+    };
+
+    let mut phase = life;
+    let mut best: Option<String> = None;
+    for _ in 0..periodicity.period {
+        let cells: Vec<(i64, i64)> = phase.cells().copied().collect();
+        for code in orientations(&cells).map(|cells| wechsler(&cells)) {
+            if best
+                .as_ref()
+                .is_none_or(|best| (code.len(), &code) < (best.len(), best))
+            {
+                best = Some(code);
+            }
+        }
+        phase.step();
+    }
+
+    (
+        format!("{prefix}{size}_{}", best.unwrap_or_default()),
+        kind,
+        Some(periodicity.period),
+    )
+}
+
+// The eight rotations and reflections of a set of cells.
+fn orientations(cells: &[(i64, i64)]) -> impl Iterator<Item = Vec<(i64, i64)>> + '_ {
+    (0..8).map(move |orientation| {
+        cells
+            .iter()
+            .map(|&(x, y)| {
+                let (x, y) = if orientation & 4 != 0 { (y, x) } else { (x, y) };
+                let x = if orientation & 1 != 0 { -x } else { x };
+                let y = if orientation & 2 != 0 { -y } else { y };
+                (x, y)
+            })
+            .collect()
+    })
+}
+
+// Encodes cells in the extended Wechsler format: strips five rows high, top
+// to bottom and separated by `z`, each a column of five cells per digit with
+// runs of empty columns shortened to `w`, `x` or `y` and a count.
+fn wechsler(cells: &[(i64, i64)]) -> String {
+    let Some(min_x) = cells.iter().map(|(x, _)| *x).min() else {
+        return String::new();
+    };
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    let max_x = cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+    let max_y = cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
+    let alive: HashSet<(i64, i64)> = cells.iter().map(|(x, y)| (x - min_x, y - min_y)).collect();
+
+    let mut code = String::new();
+    for strip in 0..=(max_y - min_y) / STRIP_HEIGHT {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut columns: Vec<usize> = (0..=max_x - min_x)
+            .map(|x| {
+                (0..STRIP_HEIGHT)
+                    .filter(|row| alive.contains(&(x, strip * STRIP_HEIGHT + row)))
+                    .map(|row| 1 << row)
+                    .sum()
+            })
+            .collect();
+        while columns.last() == Some(&0) {
+            columns.pop();
+        }
+
+        let mut zeros = 0;
+        for column in columns {
+            if column == 0 {
+                zeros += 1;
+                continue;
+            }
+            push_zeros(&mut code, zeros);
+            zeros = 0;
+            code.push(WECHSLER_DIGITS[column] as char);
+        }
+    }
+
+    code
+}
+
+fn push_zeros(code: &mut String, mut zeros: usize) {
+    while zeros > 0 {
+        match zeros {
+            1 => code.push('0'),
+            2 => code.push('w'),
+            3 => code.push('x'),
+            _ => {
+                let run = zeros.min(WECHSLER_DIGITS.len() + 3);
+                code.push('y');
+                code.push(WECHSLER_DIGITS[run - 4] as char);
+                zeros -= run;
+                continue;
+            }
+        }
+        zeros = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::rle::Rle;
+
+    fn census_of(rle: &str) -> Census {
+        let pattern = Rle::parse(rle).unwrap().pattern;
+        Census::of_cells(
+            pattern
+                .cells()
+                .filter(|(_, state)| *state == CellState::Alive)
+                .map(|((x, y), _)| (x as i64, y as i64)),
+            Rule::conway(),
+        )
+    }
+
+    fn codes(census: &Census) -> Vec<(&str, usize)> {
+        census
+            .entries
+            .iter()
+            .map(|entry| (entry.apgcode.as_str(), entry.count))
+            .collect()
+    }
+
+    #[test]
+    fn codes_well_known_objects() {
+        let cases = [
+            ("x = 2, y = 2\n2o$2o!", "xs4_33"),
+            ("x = 4, y = 3\nb2o$o2bo$b2o!", "xs6_696"),
+            ("x = 4, y = 4\nb2o$o2bo$bobo$2bo!", "xs7_2596"),
+            ("x = 3, y = 3\n2o$obo$bo!", "xs5_253"),
+            ("x = 3, y = 1\n3o!", "xp2_7"),
+            ("x = 3, y = 3\nbo$2bo$3o!", "xq4_153"),
+        ];
+        for (rle, apgcode) in cases {
+            assert_eq!(codes(&census_of(rle)), vec![(apgcode, 1)], "{rle}");
+        }
+    }
+
+    #[test]
+    fn counts_separate_objects() {
+        let census = census_of("x = 12, y = 5\n2o4b2o3bo$2o4b2o3bo2$2o$2o!");
+        assert_eq!(codes(&census), vec![("xs4_33", 3), ("zz_UNSETTLED", 1)]);
+        assert_eq!(census.object_count(), 4);
+        assert_eq!(
+            census.to_csv(),
+            "apgcode,kind,period,population,count\nxs4_33,still life,1,4,3\nzz_UNSETTLED,unsettled,,2,1\n"
+        );
+    }
+
+    #[test]
+    fn shortens_runs_of_empty_columns() {
+        assert_eq!(wechsler(&[(0, 0), (4, 0)]), "1x1");
+        assert_eq!(wechsler(&[(0, 0), (5, 0)]), "1y01");
+        assert_eq!(wechsler(&[(0, 0), (44, 0)]), "1yzy01");
+        assert_eq!(wechsler(&[(0, 0), (0, 6)]), "1z2");
+    }
+}
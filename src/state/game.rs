@@ -1,183 +1,1034 @@
-use std::{cell::RefCell, rc::Rc, time::Duration};
-
-use crate::view::ui::ControlMessages;
+use std::{cell::RefCell, io, path::Path, rc::Rc};
 
+use super::activity::{blank_heatmap, ActivityTracker, Heatmap};
+use super::builder::GameBuilder;
 use super::cell::{Cell, CellState};
+use super::history::{CellChange, Delta, History, HistoryLimit};
+use super::journal::EditJournal;
+use super::hensel;
+use super::neighborhood::{self, Shape};
+use super::pattern::{Pattern, Transform};
+use super::period::{BoardCycle, CycleDetector};
+use super::rle::Rle;
+use super::rule::Rule;
+use super::rule_file::{self, RuleFile};
+use super::selection::Selection;
+use super::simulation_controls::ControlMessages;
+use super::soup::{self, SoupParams};
+use super::topology::Topology;
+use super::watch::{TickOutcome, Watch};
+use super::wolfram::WolframRule;
 use rand::prelude::*;
-use tokio::sync::mpsc::Receiver;
-use tokio::sync::watch::Sender;
+use rand::rngs::StdRng;
 use tracing::{debug, info};
 
-const TICK_RATE_PER_SECOND: f64 = 15.0;
-type Board = Vec<Vec<Option<Cell>>>;
+// Comments written into saved RLE files so a run can be picked up again.
+const SAVE_SEED_COMMENT: &str = "seed";
+const SAVE_GENERATION_COMMENT: &str = "generation";
+pub type Board = Vec<Vec<Option<Cell>>>;
 
 #[derive(Debug, Clone, Default)]
 pub struct GameData {
     pub running: bool,
     pub cells: Board,
-    pub previous_generation: Board
+    pub previous_generation: Board,
+    pub generation: u64,
+    // Number of generations kept in the rewind history, and where on that
+    // timeline the board currently is.
+    pub history_len: usize,
+    pub history_position: usize,
+    // The last copied or cut region, shown as a preview while pasting.
+    pub clipboard: Option<Pattern>,
+    // The seed the random number generator was started from. Running again
+    // with the same seed reproduces every soup and random fill.
+    pub seed: u64,
+    // Outcome of the last operation the user should hear about, e.g. a save.
+    pub status: Option<String>,
+    // The rule and topology the board runs with, for display.
+    pub rule: String,
+    pub topology: Topology,
+    // The shape of the neighborhood the board runs, which decides whether
+    // it is drawn as a hex grid.
+    pub shape: Shape,
+    // Colors of each state given by a rule file, empty for the usual ones,
+    // and the names of those states where known.
+    pub colors: Vec<(u8, u8, u8)>,
+    pub state_names: Vec<String>,
+    // Set once the board has started repeating itself, until it is edited.
+    pub cycle: Option<BoardCycle>,
+    // How often each cell changed state over the last generations run.
+    pub activity: Heatmap,
+    // How many generations each cell has been in its current state.
+    pub ages: Vec<Vec<u64>>,
+    // Conditions that pause the run, and those met by the last generation.
+    pub watches: Vec<Watch>,
+    pub triggered: Vec<Watch>,
+    // A run of generations being computed without stopping, if any.
+    pub jump: Option<Jump>,
+}
+
+// Advancing from one generation to a later one as fast as possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+    pub from: u64,
+    pub to: u64,
+}
+
+impl Jump {
+    // How far along the jump a generation is, from 0 to 1.
+    pub fn progress(&self, generation: u64) -> f64 {
+        if self.to <= self.from {
+            return 1.0;
+        }
+
+        generation.saturating_sub(self.from) as f64 / (self.to - self.from) as f64
+    }
+}
+
+// Whether the cell at `(x, y)` is alive; cells off the board are dead.
+pub fn is_alive(board: &Board, (x, y): (usize, usize)) -> bool {
+    board
+        .get(x)
+        .and_then(|column| column.get(y))
+        .and_then(|cell| cell.as_ref())
+        .is_some_and(|cell| cell.state == CellState::Alive)
+}
+
+// Every cell zero generations old.
+fn blank_ages(size_x: isize, size_y: isize) -> Vec<Vec<u64>> {
+    vec![vec![0; size_y.max(0) as usize]; size_x.max(0) as usize]
+}
+
+// Works out a new state for every cell on the board from its position and
+// current state.
+fn map_states(board: &Board, next_state: impl Fn((usize, usize), CellState) -> CellState) -> Vec<Vec<CellState>> {
+    board
+        .iter()
+        .enumerate()
+        .map(|(x, column)| {
+            column
+                .iter()
+                .enumerate()
+                .map(|(y, cell)| next_state((x, y), cell.as_ref().map_or(CellState::Dead, |cell| cell.state)))
+                .collect()
+        })
+        .collect()
+}
+
+// A board of dead cells.
+fn blank_board(size_x: isize, size_y: isize) -> Board {
+    let mut cells = vec![vec![None; size_y.max(0) as usize]; size_x.max(0) as usize];
+    for (x, column) in cells.iter_mut().enumerate() {
+        for (y, slot) in column.iter_mut().enumerate() {
+            *slot = Some(Cell::new(x as u32, y as u32));
+        }
+    }
+
+    cells
 }
 
 #[derive(Debug)]
 pub struct Game {
     pub size_x: isize,
     pub size_y: isize,
-    sender: Option<Sender<GameData>>,
-    control_rx: Option<Receiver<ControlMessages>>,
-    game_data: Box<GameData>
+    game_data: Box<GameData>,
+    history: History,
+    journal: EditJournal,
+    // Used whenever the board is filled with a new random soup.
+    soup_params: SoupParams,
+    // Every random decision the game makes is drawn from this generator, so
+    // a run is reproducible from its seed.
+    rng: StdRng,
+    rule: Rule,
+    // A Golly rule file run instead of `rule`, if one was loaded.
+    rule_file: Option<RuleFile>,
+    // A one-dimensional rule run on the bottom row instead, if one was set.
+    wolfram: Option<WolframRule>,
+    topology: Topology,
+    cycle_detector: CycleDetector,
+    activity: ActivityTracker,
+    // Stop running as soon as the board starts repeating itself.
+    auto_pause: bool,
 }
 
 #[derive(Clone)]
 pub struct GameRef(pub Rc<RefCell<Game>>);
 
 impl Game {
-    pub fn with_sender(mut self, tx: Sender<GameData>) -> Self {
-        self.sender = Some(tx);
+    pub fn builder() -> GameBuilder {
+        GameBuilder::new()
+    }
+
+    pub fn with_history_limit(mut self, limit: HistoryLimit) -> Self {
+        self.history = History::new(limit);
 
         self
     }
 
-    pub fn with_control_rx(mut self, rx: Receiver<ControlMessages>) -> Self {
-        self.control_rx = Some(rx);
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self.rule_file = None;
+        self.wolfram = None;
+        self.game_data.rule = rule.to_string();
+        self.game_data.shape = rule.neighborhood.shape;
+        self.game_data.colors.clear();
+        self.game_data.state_names.clear();
 
         self
     }
 
-    fn randomize(&mut self) {
-        let mut cells = vec![vec![None; self.size_y as usize]; self.size_x as usize];
-        info!("Creating a randomized board.");
-        for x in 0..self.size_x as usize {
-            for y in 0..self.size_y as usize {
-                let cell = Cell::new(x as u32, y as u32);
-                cells[x][y] = Some(cell);
-                let mut rng = rand::thread_rng();
-
-                if rng.gen::<f64>() > 0.5 {
-                    cells[x][y]
-                        .as_mut()
-                        .map(|inner| inner.state = CellState::Alive);
+    // Runs the transitions of a Golly rule file instead of a B/S rule.
+    pub fn with_rule_file(mut self, rule_file: RuleFile) -> Self {
+        self.game_data.rule = rule_file.name.clone();
+        self.game_data.shape = rule_file.neighborhood.shape();
+        self.game_data.colors = rule_file.colors.clone();
+        self.game_data.state_names = rule_file.state_names.clone();
+        self.rule_file = Some(rule_file);
+        self.wolfram = None;
+
+        self
+    }
+
+    // Runs a one-dimensional rule, each generation a new bottom row with
+    // the earlier ones scrolling up. Soups and resets fill the bottom row
+    // as the rule's initial row says.
+    pub fn with_wolfram_rule(mut self, wolfram: WolframRule) -> Self {
+        self.game_data.rule = wolfram.to_string();
+        self.game_data.shape = Shape::Moore;
+        self.game_data.colors = match wolfram.states {
+            2 => Vec::new(),
+            states => (0..states.into()).map(|state| rule_file::default_color(state, states.into())).collect(),
+        };
+        self.game_data.state_names.clear();
+        self.rule_file = None;
+        self.wolfram = Some(wolfram);
+
+        self
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self.game_data.topology = topology;
+        // A shape turning up shifted only repeats forever on a torus.
+        self.cycle_detector = CycleDetector::default().with_translations(topology == Topology::Torus);
+
+        self
+    }
+
+    pub fn with_auto_pause(mut self, auto_pause: bool) -> Self {
+        self.auto_pause = auto_pause;
+
+        self
+    }
+
+    pub fn with_watch(mut self, watch: Watch) -> Self {
+        self.add_watch(watch);
+
+        self
+    }
+
+    pub fn with_running(mut self, running: bool) -> Self {
+        self.game_data.running = running;
+
+        self
+    }
+
+    // Replaces the board with a pattern centered on it. Whatever does not
+    // fit is cropped.
+    pub fn with_pattern(mut self, pattern: &Pattern) -> Self {
+        let mut cells = blank_board(self.size_x, self.size_y);
+        let offset_x = (self.size_x - pattern.width as isize) / 2;
+        let offset_y = (self.size_y - pattern.height as isize) / 2;
+        for ((x, y), state) in pattern.cells() {
+            let pos = (x as isize + offset_x, y as isize + offset_y);
+            if let Some((x, y)) = Topology::Bounded.wrap(pos, (self.size_x, self.size_y)) {
+                if let Some(cell) = cells[x][y].as_mut() {
+                    cell.state = state;
                 }
             }
         }
-
-        // Overwrite existing cell data
+        self.game_data.previous_generation = cells.clone();
         self.game_data.cells = cells;
+
+        self
+    }
+
+    // Takes over the board, generation and seed of an earlier game. The
+    // builder checks the board matches the game's size.
+    pub(super) fn with_game_data(mut self, game_data: GameData) -> Self {
+        let seed = game_data.seed;
+        self.game_data = Box::new(GameData {
+            rule: self.game_data.rule.clone(),
+            shape: self.game_data.shape,
+            colors: self.game_data.colors.clone(),
+            state_names: self.game_data.state_names.clone(),
+            topology: self.topology,
+            activity: blank_heatmap(self.size_x, self.size_y),
+            ages: blank_ages(self.size_x, self.size_y),
+            ..game_data
+        });
+        self.activity = ActivityTracker::default();
+        self.reseed(seed);
+        self.history.clear();
+        self.journal.clear();
+        self.sync_history();
+
+        self
+    }
+
+    // Restarts the random number generator from `seed`. Set this before
+    // the soup so the soup comes from the seed too.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.reseed(seed);
+
+        self
+    }
+
+    // Fills the board with a random soup, also used by later resets.
+    pub fn with_soup(mut self, soup_params: SoupParams) -> Self {
+        self.soup_params = soup_params;
+        self.randomize();
+        self.game_data.previous_generation = self.game_data.cells.clone();
+
+        self
     }
 
-    pub fn randomized_board(size_x: isize, size_y: isize) -> Self {
-        let mut cells = vec![vec![None; size_y as usize]; size_x as usize];
-        info!("Creating a randomized board.");
-        for x in 0_usize..size_x as usize {
-            for y in 0_usize..size_y as usize {
-                let cell = Cell::new(x as u32, y as u32);
-                cells[x][y] = Some(cell);
+    // Sets the soup later resets fill the board with, leaving the board as
+    // it is.
+    pub fn with_reset_soup(mut self, soup_params: SoupParams) -> Self {
+        self.soup_params = soup_params;
+
+        self
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        info!("Seeding the random number generator with {seed}.");
+        self.rng = StdRng::seed_from_u64(seed);
+        self.game_data.seed = seed;
+    }
+
+    fn randomize(&mut self) {
+        let mut cells = blank_board(self.size_x, self.size_y);
+        if let Some(wolfram) = self.wolfram.as_ref() {
+            info!("Starting {wolfram} from a {} row.", wolfram.initial);
+            let row = wolfram.initial_row(cells.len(), self.soup_params.density, &mut self.rng);
+            for (column, state) in cells.iter_mut().zip(row) {
+                if let Some(Some(cell)) = column.last_mut() {
+                    cell.state = state;
+                }
             }
+            self.game_data.cells = cells;
+            return;
         }
+        info!("Creating a randomized board with {:?}.", self.soup_params);
+        // A soup with its own seed is reproducible on its own; otherwise it
+        // comes from the game's generator.
+        let board_size = (self.size_x as u32, self.size_y as u32);
+        let (soup, (offset_x, offset_y)) = match self.soup_params.seed {
+            Some(seed) => soup::generate(&self.soup_params, board_size, &mut StdRng::seed_from_u64(seed)),
+            None => soup::generate(&self.soup_params, board_size, &mut self.rng),
+        };
+        for ((x, y), state) in soup.cells() {
+            if let Some(Some(cell)) = cells
+                .get_mut((x + offset_x) as usize)
+                .and_then(|column| column.get_mut((y + offset_y) as usize))
+            {
+                cell.state = state;
+            }
+        }
+
+        // Overwrite existing cell data
+        self.game_data.cells = cells;
+    }
 
-        let mut init = Game {
+    // This skips the builder's checks, so outside the engine games are made
+    // with `GameBuilder`.
+    pub(super) fn empty_board(size_x: isize, size_y: isize) -> Self {
+        let cells = blank_board(size_x, size_y);
+        let rule = Rule::default();
+        let seed = rand::random::<u64>();
+        Game {
             size_x,
             size_y,
-            sender: None,
-            control_rx: None,
             game_data: Box::new(GameData {
                 running: false,
                 cells: cells.clone(),
-                previous_generation: cells
-            })
+                previous_generation: cells,
+                seed,
+                rule: rule.to_string(),
+                activity: blank_heatmap(size_x, size_y),
+                ages: blank_ages(size_x, size_y),
+                ..Default::default()
+            }),
+            history: History::new(HistoryLimit::default()),
+            journal: EditJournal::default(),
+            soup_params: SoupParams::default(),
+            rng: StdRng::seed_from_u64(seed),
+            rule,
+            rule_file: None,
+            wolfram: None,
+            topology: Topology::default(),
+            cycle_detector: CycleDetector::default(),
+            activity: ActivityTracker::default(),
+            auto_pause: false,
+        }
+    }
+
+    // Carries out a command, e.g. one sent by the terminal interface.
+    pub fn handle_control_message(&mut self, control_message: ControlMessages) {
+        match control_message {
+            ControlMessages::Stop => {
+                self.game_data.running = false;
+                self.game_data.jump = None;
+            }
+            ControlMessages::Start => self.game_data.running = true,
+            ControlMessages::Reset => self.reset(),
+            ControlMessages::ResetSoup(soup_params) => {
+                self.soup_params = soup_params;
+                self.reset();
+            }
+            ControlMessages::Step => self.tick(),
+            ControlMessages::Advance(generations) => self.advance(generations),
+            ControlMessages::JumpTo(generation) => self.jump_to(generation),
+            ControlMessages::CancelJump => {
+                if let Some(jump) = self.game_data.jump.take() {
+                    info!("Jump to generation {} cancelled at {}.", jump.to, self.game_data.generation);
+                }
+            }
+            ControlMessages::StepBack => {
+                self.game_data.running = false;
+                self.step_back();
+            }
+            ControlMessages::SeekHistory(position) => {
+                self.game_data.running = false;
+                self.seek_history(position);
+            }
+            ControlMessages::BeginStroke => {
+                // A stroke whose end never arrived should not swallow this one.
+                self.journal.close_batch();
+                self.journal.begin_batch();
+            }
+            ControlMessages::Paint { pos, state } => self.set_cell(pos, state),
+            ControlMessages::EndStroke => self.journal.end_batch(),
+            ControlMessages::Undo => {
+                if !self.journal.undo(&mut self.game_data.cells) {
+                    info!("Nothing to undo.");
+                }
+                self.after_edit();
+            }
+            ControlMessages::Redo => {
+                if !self.journal.redo(&mut self.game_data.cells) {
+                    info!("Nothing to redo.");
+                }
+                self.after_edit();
+            }
+            ControlMessages::Copy(selection) => self.copy(&selection),
+            ControlMessages::Cut(selection) => {
+                self.copy(&selection);
+                self.clear(&selection);
+            }
+            ControlMessages::Paste(pos) => {
+                if let Some(pattern) = self.game_data.clipboard.clone() {
+                    self.stamp(&pattern, pos);
+                }
+            }
+            ControlMessages::TransformSelection(selection, transform) => {
+                self.transform_selection(&selection, transform)
+            }
+            ControlMessages::TransformClipboard(transform) => {
+                self.game_data.clipboard = self
+                    .game_data
+                    .clipboard
+                    .as_ref()
+                    .map(|pattern| pattern.transformed(transform));
+            }
+            ControlMessages::Stamp(pattern, pos) => self.stamp(&pattern, pos),
+            ControlMessages::Save(path) => {
+                let status = match self.save(&path) {
+                    Ok(()) => format!("Saved to {}.", path.display()),
+                    Err(error) => format!("Unable to save {}: {error}", path.display()),
+                };
+                info!("{status}");
+                self.game_data.status = Some(status);
+            }
+            ControlMessages::Load(path) => {
+                let status = match self.load(&path) {
+                    Ok(()) => format!("Loaded {}.", path.display()),
+                    Err(error) => format!("Unable to load {}: {error}", path.display()),
+                };
+                info!("{status}");
+                self.game_data.status = Some(status);
+            }
+            ControlMessages::ResetActivity => self.reset_activity(),
+            ControlMessages::AddWatch(watch) => self.add_watch(watch),
+            ControlMessages::RemoveWatch(watch) => {
+                self.game_data.watches.retain(|existing| *existing != watch);
+                self.game_data.triggered.retain(|triggered| *triggered != watch);
+            }
+            ControlMessages::Clear(selection) => self.clear(&selection),
+            ControlMessages::FillRandom(selection, density) => self.fill_random(&selection, density),
+        }
+    }
+
+    // Writes the board as an RLE file, recording the seed and generation in
+    // its comments.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_rle().to_string())
+    }
+
+    // The whole board as an RLE pattern with its rule, seed and generation.
+    pub fn to_rle(&self) -> Rle {
+        let pattern = match (self.size_x as u32).checked_sub(1).zip((self.size_y as u32).checked_sub(1)) {
+            Some(bottom_right) => Pattern::from_board(&self.game_data.cells, &Selection::new((0, 0), bottom_right)),
+            None => Pattern::new(0, 0),
         };
+        let mut rle = Rle::new(pattern);
+        rle.rule = Some(self.game_data.rule.clone());
+        rle.comments = vec![
+            "Saved by rust-of-life".to_string(),
+            format!("{SAVE_SEED_COMMENT} {}", self.game_data.seed),
+            format!("{SAVE_GENERATION_COMMENT} {}", self.game_data.generation),
+        ];
 
-        init.randomize();
+        rle
+    }
 
-        init
+    pub fn game_data(&self) -> &GameData {
+        &self.game_data
     }
 
-    pub async fn start(mut self) {
-        if let Some(sender) = self.sender.clone() {
-            let _ = sender.send(*self.game_data.clone());
-        }
+    pub fn generation(&self) -> u64 {
+        self.game_data.generation
+    }
 
-        loop {
-            if let Some(sender) = self.sender.clone() {
-                let _ = sender.send(*self.game_data.clone());
-            }
-            if self.game_data.running {
-                tracing::debug!("Simulation running");
-                let tick_time: f64 = (1.0 / TICK_RATE_PER_SECOND) * 1000.0;
-                tokio::time::sleep(Duration::from_millis(tick_time as u64)).await;
-                self.tick();
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    // State of the cell at `pos`, or `None` if it is off the board.
+    pub fn cell_state(&self, (x, y): (u32, u32)) -> Option<CellState> {
+        self.game_data
+            .cells
+            .get(x as usize)
+            .and_then(|column| column.get(y as usize))
+            .and_then(|cell| cell.as_ref())
+            .map(|cell| cell.state)
+    }
+
+    // Positions of the live cells, column by column.
+    pub fn alive_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.game_data
+            .cells
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|cell| cell.state == CellState::Alive)
+            .map(|cell| cell.pos)
+    }
+
+    pub fn population(&self) -> usize {
+        self.alive_cells().count()
+    }
+
+    // Replaces the board with an RLE file, restoring the seed and generation
+    // if it was saved by this game.
+    pub fn load(&mut self, path: &Path) -> io::Result<()> {
+        let contents = std::fs::read_to_string(path)?;
+        let rle = Rle::parse(&contents).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let saved_value = |key: &str| {
+            rle.comments
+                .iter()
+                .find_map(|comment| comment.strip_prefix(key)?.trim().parse::<u64>().ok())
+        };
+
+        self.game_data.running = false;
+        self.game_data.jump = None;
+        for cell in self.game_data.cells.iter_mut().flatten().flatten() {
+            cell.kill();
+        }
+        for ((x, y), state) in rle.pattern.cells() {
+            if let Some(Some(cell)) = self
+                .game_data
+                .cells
+                .get_mut(x as usize)
+                .and_then(|column| column.get_mut(y as usize))
+            {
+                cell.state = state;
             }
+        }
+        if let Some(seed) = saved_value(SAVE_SEED_COMMENT) {
+            self.reseed(seed);
+        }
+        self.game_data.generation = saved_value(SAVE_GENERATION_COMMENT).unwrap_or(0);
+        self.game_data.previous_generation = self.game_data.cells.clone();
+        self.history.clear();
+        self.journal.clear();
+        self.forget_cycle();
+        self.reset_activity();
+        self.game_data.ages = blank_ages(self.size_x, self.size_y);
+        self.sync_history();
 
-            if let Some(controls_tx) = self.control_rx.as_mut() {
-                let control_message = controls_tx.try_recv();
+        Ok(())
+    }
 
-                if let Ok(control_message) = control_message {
-                    tracing::info!("Control message received: {:?}", control_message);
-                    match control_message {
-                        ControlMessages::Stop => self.game_data.running = false,
-                        ControlMessages::Start => self.game_data.running = true,
-                        ControlMessages::Reset => self.reset(),
-                        ControlMessages::Step => self.tick(),
-                    }
-                }
+    fn copy(&mut self, selection: &Selection) {
+        self.game_data.clipboard = Some(Pattern::from_board(&self.game_data.cells, selection));
+    }
+
+    // Writes a pattern onto the board with its top left corner at `pos`,
+    // overwriting the cells underneath. The whole stamp is one undo unit.
+    pub fn stamp(&mut self, pattern: &Pattern, (x, y): (u32, u32)) {
+        self.journal.begin_batch();
+        for ((offset_x, offset_y), state) in pattern.cells() {
+            self.write_cell((x + offset_x, y + offset_y), state);
+        }
+        self.journal.end_batch();
+        self.after_edit();
+    }
+
+    fn clear(&mut self, selection: &Selection) {
+        self.journal.begin_batch();
+        for pos in selection.positions() {
+            self.write_cell(pos, CellState::Dead);
+        }
+        self.journal.end_batch();
+        self.after_edit();
+    }
+
+    // Transforms the selected region in place, keeping its top left corner.
+    fn transform_selection(&mut self, selection: &Selection, transform: Transform) {
+        let pattern = Pattern::from_board(&self.game_data.cells, selection).transformed(transform);
+        self.journal.begin_batch();
+        self.clear(selection);
+        self.stamp(&pattern, selection.top_left());
+        self.journal.end_batch();
+    }
+
+    fn fill_random(&mut self, selection: &Selection, density: f64) {
+        self.journal.begin_batch();
+        for pos in selection.positions() {
+            let state = if self.rng.gen::<f64>() < density {
+                CellState::Alive
+            } else {
+                CellState::Dead
+            };
+            self.write_cell(pos, state);
+        }
+        self.journal.end_batch();
+        self.after_edit();
+    }
+
+    // Sets a single cell as a user edit, recording it in the edit journal.
+    pub fn set_cell(&mut self, pos: (u32, u32), state: CellState) {
+        self.write_cell(pos, state);
+        self.after_edit();
+    }
+
+    // Same as `set_cell` but leaves the follow-up to the caller, so bulk
+    // edits only pay for it once.
+    fn write_cell(&mut self, (x, y): (u32, u32), state: CellState) {
+        let Some(Some(cell)) = self
+            .game_data
+            .cells
+            .get_mut(x as usize)
+            .and_then(|column| column.get_mut(y as usize))
+        else {
+            debug!("Ignoring edit outside of the board at x: {x} y: {y}");
+            return;
+        };
+        let before = cell.state;
+        cell.state = state;
+        if before != state {
+            if let Some(age) = self.game_data.ages.get_mut(x as usize).and_then(|column| column.get_mut(y as usize)) {
+                *age = 0;
             }
         }
+        self.journal.record(CellChange {
+            pos: (x, y),
+            before,
+            after: state,
+        });
+    }
+
+    // The board no longer follows from the stored generations, before or
+    // after it: stepping back would carry the edit into generations that
+    // never had it. The previous generation should not show edits as
+    // births/deaths either.
+    fn after_edit(&mut self) {
+        self.history.clear();
+        self.game_data.previous_generation = self.game_data.cells.clone();
+        self.forget_cycle();
+        self.sync_history();
     }
 
     fn reset(&mut self) {
         self.game_data.running = false; // Stop running
+        self.game_data.jump = None;
         self.randomize();
+        self.game_data.previous_generation = self.game_data.cells.clone();
+        self.game_data.generation = 0;
+        self.history.clear();
+        self.journal.clear();
+        self.forget_cycle();
+        self.reset_activity();
+        self.game_data.ages = blank_ages(self.size_x, self.size_y);
+        self.sync_history();
+    }
+
+    fn step_back(&mut self) {
+        let before = self.game_data.cells.clone();
+        if self.history.step_back(&mut self.game_data.cells) {
+            self.age_cells(-1, &Delta::between(&before, &self.game_data.cells));
+            self.game_data.previous_generation = before;
+            self.game_data.generation = self.game_data.generation.saturating_sub(1);
+            self.reset_activity();
+        } else {
+            info!("No earlier generation left in history.");
+        }
+        self.forget_cycle();
+        self.sync_history();
+    }
+
+    fn seek_history(&mut self, position: usize) {
+        let before = self.game_data.cells.clone();
+        let moved = self.history.seek(&mut self.game_data.cells, position);
+        if moved != 0 {
+            self.age_cells(moved as i64, &Delta::between(&before, &self.game_data.cells));
+            self.game_data.previous_generation = before;
+            self.game_data.generation = self.game_data.generation.saturating_add_signed(moved as i64);
+            // The heatmap counted generations the board is no longer at.
+            self.reset_activity();
+        }
+        self.forget_cycle();
+        self.sync_history();
+    }
+
+    // The board was changed other than by ticking, so whatever cycle it was
+    // in no longer applies.
+    fn forget_cycle(&mut self) {
+        self.cycle_detector.clear();
+        self.game_data.cycle = None;
+    }
+
+    // Looks for the board repeating an earlier generation, pausing when it
+    // does if asked to.
+    fn detect_cycle(&mut self) {
+        if self.game_data.cycle.is_some() {
+            return;
+        }
+        let Some(cycle) = self
+            .cycle_detector
+            .observe(&self.game_data.cells, self.game_data.generation)
+        else {
+            return;
+        };
+
+        info!("Board became periodic: {:?}.", cycle);
+        self.game_data.cycle = Some(cycle);
+        if self.auto_pause {
+            self.game_data.running = false;
+        }
+    }
+
+    // Moves every cell's age on by `generations`, which is negative when
+    // rewinding, and makes the cells that changed state new again.
+    fn age_cells(&mut self, generations: i64, delta: &Delta) {
+        for age in self.game_data.ages.iter_mut().flatten() {
+            *age = age.saturating_add_signed(generations);
+        }
+        for change in delta.changes() {
+            let (x, y) = change.pos;
+            if let Some(age) = self.game_data.ages.get_mut(x as usize).and_then(|column| column.get_mut(y as usize)) {
+                *age = 0;
+            }
+        }
+    }
+
+    fn add_watch(&mut self, watch: Watch) {
+        if !self.game_data.watches.contains(&watch) {
+            self.game_data.watches.push(watch);
+        }
+    }
+
+    // Pauses the run if the generation just computed meets any watch.
+    fn check_watches(&mut self, previous_population: usize, delta: &Delta, became_periodic: bool) {
+        let outcome = TickOutcome {
+            previous_population,
+            population: self.population(),
+            generation: self.game_data.generation,
+            delta,
+            became_periodic,
+        };
+        self.game_data.triggered = self
+            .game_data
+            .watches
+            .iter()
+            .filter(|watch| watch.is_met(&outcome))
+            .copied()
+            .collect();
+        if !self.game_data.triggered.is_empty() {
+            info!("Watches met at generation {}: {:?}.", outcome.generation, self.game_data.triggered);
+            self.game_data.running = false;
+        }
+    }
+
+    // Starts counting cell activity afresh.
+    fn reset_activity(&mut self) {
+        self.activity.reset(&mut self.game_data.activity);
+    }
+
+    fn sync_history(&mut self) {
+        self.game_data.history_len = self.history.len();
+        self.game_data.history_position = self.history.position();
+    }
+
+    // Starts a jump `generations` ahead, pausing the run. `jump_tick` then
+    // moves the board along it.
+    pub fn advance(&mut self, generations: u64) {
+        self.jump_to(self.game_data.generation.saturating_add(generations));
+    }
+
+    // Starts a jump to generation `to`, pausing the run. A generation the
+    // board is already at or past leaves it where it is.
+    pub fn jump_to(&mut self, to: u64) {
+        self.game_data.running = false;
+        let from = self.game_data.generation;
+        if to <= from {
+            let status = format!("Already at generation {from}, past {to}.");
+            info!("{status}");
+            self.game_data.status = Some(status);
+            return;
+        }
+        self.game_data.jump = Some(Jump { from, to });
+    }
+
+    pub fn is_jumping(&self) -> bool {
+        self.game_data.jump.is_some()
+    }
+
+    // Advances one generation of the current jump, ending it once its last
+    // generation is reached or a watch is met.
+    pub fn jump_tick(&mut self) {
+        let Some(jump) = self.game_data.jump else {
+            return;
+        };
+        if self.game_data.generation < jump.to {
+            self.tick();
+        }
+        if self.game_data.generation >= jump.to || !self.game_data.triggered.is_empty() {
+            debug!("Jump to generation {} ended at {}.", jump.to, self.game_data.generation);
+            self.game_data.jump = None;
+        }
     }
 
-    fn tick(&mut self) {
+    // Advances the board by one generation.
+    pub fn tick(&mut self) {
         debug!("Ticking simulation.");
+        if self.cycle_detector.is_empty() {
+            self.detect_cycle();
+        }
+        self.game_data.triggered.clear();
+        let previous_population = if self.game_data.watches.is_empty() {
+            0
+        } else {
+            self.population()
+        };
+        let was_periodic = self.game_data.cycle.is_some();
         self.game_data.previous_generation = self.game_data.cells.clone();
         let cloned_cells = self.game_data.cells.clone();
+        let (rule, topology) = (self.rule, self.topology);
+
+        // Isotropic rules look up where the live neighbors are rather than
+        // how many there are, and rule files the state of every neighbor.
+        let next_states = match (self.rule_file.as_mut(), self.wolfram.as_ref()) {
+            (Some(rule_file), _) => rule_file.next_states(&cloned_cells, topology),
+            (None, Some(wolfram)) => wolfram.next_states(&cloned_cells, topology),
+            (None, None) if rule.is_totalistic() => {
+                let live_counts = neighborhood::live_counts(&cloned_cells, rule.neighborhood, topology);
+                map_states(&cloned_cells, |(x, y), state| rule.next_state(state, live_counts[x][y] as usize))
+            }
+            (None, None) => {
+                let configurations = hensel::live_configurations(&cloned_cells, topology);
+                map_states(&cloned_cells, |(x, y), state| {
+                    rule.next_state_of_configuration(state, configurations[x][y])
+                })
+            }
+        };
 
         self.game_data.cells.iter_mut().enumerate().for_each(|(i, column)| {
             column.iter_mut().enumerate().for_each(|(j, cell)| {
-                let mut alive_count = 0;
-                for delta_i in -1_isize..=1 {
-                    for delta_j in -1_isize..=1 {
-                        // Don't count the cell itself
-                        if delta_i == 0 && delta_j == 0 {
-                            continue;
-                        }
-                        let neighbor_i = i as isize + delta_i;
-                        let neighbor_j = j as isize + delta_j;
-
-
-
-                        if neighbor_i < 0 || neighbor_i >= self.size_x {
-                            debug!("X is out of bounds. X: {}", neighbor_i);
-                            continue;
-                        }
-                        if neighbor_j < 0 || neighbor_j >= self.size_y {
-                            debug!("Y is out of bounds. Y: {}", neighbor_j);
-                            continue;
-                        }
-                        cloned_cells[neighbor_i as usize][neighbor_j as usize]
-                            .as_ref()
-                            .map(|inner| {
-                                if let CellState::Alive = inner.state {
-                                    alive_count += 1;
-                                }
-                            });
-                    }
-                }
+                debug!("Updating cell state at x: {i} y: {j}.");
 
-                debug!("Alive count for cell at x: {i} y: {j} is {alive_count}");
-                debug!("Updating cell state.");
-
-                if let CellState::Alive =
-                    cell.as_ref().map_or(&CellState::Dead, |inner| &inner.state)
-                {
-                    if alive_count < 2 || alive_count > 3 {
-                        cell.as_mut().map(|inner| inner.state = CellState::Dead);
-                    }
-                } else if alive_count == 3 {
-                    cell.as_mut().map(|inner| inner.state = CellState::Alive);
+                if let Some(inner) = cell.as_mut() {
+                    inner.state = next_states[i][j];
                 }
             })
         });
+
+        let delta = Delta::between(&self.game_data.previous_generation, &self.game_data.cells);
+        self.activity.record(&delta, &mut self.game_data.activity);
+        self.age_cells(1, &delta);
+        self.game_data.generation += 1;
+        self.detect_cycle();
+        if !self.game_data.watches.is_empty() {
+            let became_periodic = !was_periodic && self.game_data.cycle.is_some();
+            self.check_watches(previous_population, &delta, became_periodic);
+        }
+        self.history.push(delta);
+        self.sync_history();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::sparse::SparseLife;
+
+    #[test]
+    fn the_same_seed_replays_the_same_run() {
+        let mut first = Game::empty_board(32, 32).with_seed(42).with_soup(SoupParams::default());
+        let mut second = Game::empty_board(32, 32).with_seed(42).with_soup(SoupParams::default());
+        assert!(first.alive_cells().eq(second.alive_cells()));
+
+        let selection = Selection::new((0, 0), (7, 7));
+        first.fill_random(&selection, 0.3);
+        second.fill_random(&selection, 0.3);
+        first.reset();
+        second.reset();
+        assert!(first.alive_cells().eq(second.alive_cells()));
+    }
+
+    #[test]
+    fn notices_a_spaceship_coming_around_a_torus() {
+        let glider = Rle::parse("x = 3, y = 3\nbo$2bo$3o!").unwrap().pattern;
+        let mut game = Game::builder()
+            .with_size(8, 8)
+            .with_topology(Topology::Torus)
+            .with_pattern(glider)
+            .with_running(true)
+            .with_auto_pause(true)
+            .build()
+            .unwrap();
+        while game.game_data.running && game.generation() < 100 {
+            game.tick();
+        }
+
+        let cycle = game.game_data.cycle.expect("the glider should be found to repeat");
+        assert_eq!((cycle.period, cycle.onset, cycle.displacement), (4, 0, (1, 1)));
+        assert_eq!(game.generation(), 4);
+    }
+
+    #[test]
+    fn isotropic_rules_match_running_on_an_unbounded_plane() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        let mut game = Game::empty_board(48, 48).with_seed(5).with_rule(rule);
+        game.fill_random(&Selection::new((20, 20), (27, 27)), 0.4);
+        let mut life = SparseLife::from_cells(game.alive_cells().map(|(x, y)| (x as i64, y as i64))).with_rule(rule);
+        for _ in 0..6 {
+            game.tick();
+            life.step();
+            let mut expected: Vec<_> = life.cells().map(|(x, y)| (*x as u32, *y as u32)).collect();
+            expected.sort_unstable();
+            assert_eq!(game.alive_cells().collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn an_empty_board_saves_as_an_empty_pattern() {
+        let game = Game::empty_board(0, 0);
+        assert_eq!((game.to_rle().pattern.width, game.to_rle().pattern.height), (0, 0));
+    }
+
+    #[test]
+    fn edits_are_not_stepped_back_into_earlier_generations() {
+        let mut game = Game::empty_board(16, 16).with_seed(5).with_soup(SoupParams::default());
+        game.tick();
+        game.tick();
+        game.set_cell((0, 0), CellState::Alive);
+        game.handle_control_message(ControlMessages::StepBack);
+        assert_eq!((game.generation(), game.game_data().history_len), (2, 0));
+        assert_eq!(game.cell_state((0, 0)), Some(CellState::Alive));
+    }
+
+    #[test]
+    fn rewinding_clears_the_heatmap() {
+        let mut game = Game::empty_board(16, 16).with_seed(5).with_soup(SoupParams::default());
+        let activity = |game: &Game| game.game_data().activity.iter().flatten().sum::<u32>();
+        for _ in 0..3 {
+            game.tick();
+        }
+        assert!(activity(&game) > 0);
+        game.handle_control_message(ControlMessages::StepBack);
+        assert_eq!(activity(&game), 0);
+
+        game.tick();
+        assert!(activity(&game) > 0);
+        game.handle_control_message(ControlMessages::SeekHistory(0));
+        assert_eq!((game.generation(), activity(&game)), (0, 0));
+    }
+
+    #[test]
+    fn jumps_stop_at_their_target_or_a_watch() {
+        let mut game = Game::empty_board(16, 16).with_seed(7).with_soup(SoupParams::default());
+        game.handle_control_message(ControlMessages::Advance(25));
+        while game.is_jumping() {
+            game.jump_tick();
+        }
+        assert_eq!(game.generation(), 25);
+
+        game = game.with_watch(Watch::Generation(30));
+        game.handle_control_message(ControlMessages::Advance(100));
+        while game.is_jumping() {
+            game.jump_tick();
+        }
+        assert_eq!(game.generation(), 30);
+
+        game.handle_control_message(ControlMessages::Advance(100));
+        game.jump_tick();
+        game.handle_control_message(ControlMessages::CancelJump);
+        assert!(!game.is_jumping());
+        assert_eq!(game.generation(), 31);
+
+        // A jump to a generation ends there even if the board moved on
+        // since it was asked for.
+        game.tick();
+        game.handle_control_message(ControlMessages::JumpTo(40));
+        while game.is_jumping() {
+            game.jump_tick();
+        }
+        assert_eq!(game.generation(), 40);
+        game.handle_control_message(ControlMessages::JumpTo(35));
+        assert!(!game.is_jumping());
+    }
+
+    #[test]
+    fn pauses_on_the_first_watch_met() {
+        let blinker = Rle::parse("x = 3, y = 1\n3o!").unwrap().pattern;
+        let mut game = Game::builder()
+            .with_size(7, 7)
+            .with_pattern(blinker)
+            .with_running(true)
+            .build()
+            .unwrap()
+            .with_watch(Watch::Generation(5))
+            .with_watch(Watch::RegionChanged(Selection::new((3, 0), (3, 1))))
+            .with_watch(Watch::Periodic);
+        game.tick();
+        assert!(game.game_data.running);
+
+        game.tick();
+        assert_eq!(game.game_data.triggered, vec![Watch::Periodic]);
+        assert!(!game.game_data.running);
+
+        game.handle_control_message(ControlMessages::AddWatch(Watch::PopulationBelow(3)));
+        game.handle_control_message(ControlMessages::RemoveWatch(Watch::Periodic));
+        while game.game_data.triggered.is_empty() {
+            game.tick();
+        }
+        assert_eq!(game.generation(), 5);
     }
 }
@@ -0,0 +1,153 @@
+use std::sync::OnceLock;
+
+use super::cell::CellState;
+use super::game::Board;
+use super::topology::Topology;
+
+// The eight neighbors in the order their bits appear in a configuration:
+// N, NE, E, SE, S, SW, W, NW, with y growing downwards.
+pub const RING: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+// One configuration of each letter for up to four live neighbors, as bits
+// of `RING`. Five to seven neighbors are the complements of three to one.
+const REPRESENTATIVES: [(u8, char, u8); 31] = [
+    (1, 'c', 0b0000_0010),
+    (1, 'e', 0b0000_0001),
+    (2, 'c', 0b0000_1010),
+    (2, 'e', 0b0000_0101),
+    (2, 'k', 0b0000_1001),
+    (2, 'a', 0b0000_0011),
+    (2, 'i', 0b0001_0001),
+    (2, 'n', 0b0010_0010),
+    (3, 'c', 0b0010_1010),
+    (3, 'e', 0b0001_0101),
+    (3, 'k', 0b0010_0101),
+    (3, 'a', 0b0000_0111),
+    (3, 'i', 0b1000_0011),
+    (3, 'n', 0b0000_1011),
+    (3, 'y', 0b0010_1001),
+    (3, 'q', 0b0010_0011),
+    (3, 'j', 0b0100_0011),
+    (3, 'r', 0b0001_0011),
+    (4, 'c', 0b1010_1010),
+    (4, 'e', 0b0101_0101),
+    (4, 'k', 0b0100_1011),
+    (4, 'a', 0b0000_1111),
+    (4, 'i', 0b0001_1011),
+    (4, 'n', 0b1000_1011),
+    (4, 'y', 0b0010_1011),
+    (4, 'q', 0b0010_0111),
+    (4, 'j', 0b0101_0011),
+    (4, 'r', 0b0001_0111),
+    (4, 't', 0b0011_1001),
+    (4, 'w', 0b0110_0011),
+    (4, 'z', 0b0011_0011),
+];
+
+// The letters used with each number of live neighbors, in the order rules
+// are written in.
+pub fn letters(count: u8) -> &'static str {
+    match count {
+        1 | 7 => "ce",
+        2 | 6 => "cekain",
+        3 | 5 => "cekainyqjr",
+        4 => "cekainyqjrtwz",
+        _ => "",
+    }
+}
+
+// Every configuration the rotations and reflections of `configuration` give.
+fn symmetries(configuration: u8) -> impl Iterator<Item = u8> {
+    (0..8).map(move |symmetry: usize| {
+        (0..8)
+            .filter(|bit| configuration & 1 << bit != 0)
+            .map(|bit| {
+                // Rotating a quarter turn moves two places round the ring;
+                // reflecting mirrors it about the N-S axis.
+                let bit = if symmetry >= 4 { (8 - bit) % 8 } else { bit };
+                1 << ((bit + 2 * (symmetry % 4)) % 8)
+            })
+            .fold(0, |mirrored, bit| mirrored | bit)
+    })
+}
+
+// The number of live neighbors and letter of every configuration.
+fn classes() -> &'static [(u8, Option<char>); 256] {
+    static CLASSES: OnceLock<[(u8, Option<char>); 256]> = OnceLock::new();
+    CLASSES.get_or_init(|| {
+        let mut classes = [(0, None); 256];
+        for (configuration, class) in classes.iter_mut().enumerate() {
+            *class = (configuration.count_ones() as u8, None);
+        }
+        for (count, letter, representative) in REPRESENTATIVES {
+            for configuration in symmetries(representative) {
+                classes[configuration as usize] = (count, Some(letter));
+                if count < 4 {
+                    classes[!configuration as usize] = (8 - count, Some(letter));
+                }
+            }
+        }
+
+        classes
+    })
+}
+
+// The number of live neighbors in a configuration and its letter, if that
+// number has any.
+pub fn class_of(configuration: u8) -> (u8, Option<char>) {
+    classes()[configuration as usize]
+}
+
+// The configurations written as `count` followed by `letter`.
+pub fn configurations(count: u8, letter: char) -> impl Iterator<Item = u8> {
+    (0..=255).filter(move |configuration| class_of(*configuration) == (count, Some(letter)))
+}
+
+// The configuration of live neighbors around every cell on the board,
+// indexed `[x][y]` like the board.
+pub fn live_configurations(board: &Board, topology: Topology) -> Vec<Vec<u8>> {
+    let size = (board.len() as isize, board.first().map_or(0, |column| column.len()) as isize);
+    (0..size.0)
+        .map(|x| {
+            (0..size.1)
+                .map(|y| {
+                    RING.iter()
+                        .enumerate()
+                        .filter_map(|(bit, (delta_x, delta_y))| {
+                            let (x, y) = topology.wrap((x + delta_x, y + delta_y), size)?;
+                            board[x][y]
+                                .as_ref()
+                                .filter(|cell| cell.state == CellState::Alive)
+                                .map(|_| 1 << bit)
+                        })
+                        .fold(0, |configuration, bit| configuration | bit)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_letter_names_a_distinct_class() {
+        for count in 0..=8 {
+            let with_count = (0..=255_u8).filter(|configuration| configuration.count_ones() == count as u32);
+            let named: usize = letters(count)
+                .chars()
+                .map(|letter| configurations(count, letter).count())
+                .sum();
+            assert_eq!(
+                named,
+                if letters(count).is_empty() { 0 } else { with_count.count() },
+                "{count}"
+            );
+        }
+        assert_eq!(configurations(2, 'a').count(), 8);
+        assert_eq!(configurations(4, 'e').collect::<Vec<_>>(), vec![0b0101_0101]);
+        assert_eq!(class_of(0b1111_1000), (5, Some('a')));
+        assert_eq!(class_of(0xff), (8, None));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+
+use super::cell::CellState;
+use super::game::Board;
+
+const DEFAULT_MAX_GENERATIONS: usize = 256;
+
+// A single cell that changed state between two consecutive generations.
+#[derive(Debug, Clone)]
+pub struct CellChange {
+    pub pos: (u32, u32),
+    pub before: CellState,
+    pub after: CellState,
+}
+
+// The difference between two consecutive generations. Only the cells that
+// changed are stored, so a mostly static board costs almost nothing to keep.
+#[derive(Debug, Clone, Default)]
+pub struct Delta {
+    changes: Vec<CellChange>,
+}
+
+impl Delta {
+    pub fn between(before: &Board, after: &Board) -> Self {
+        let mut changes = Vec::new();
+        for (old_column, new_column) in before.iter().zip(after.iter()) {
+            for (old_cell, new_cell) in old_column.iter().zip(new_column.iter()) {
+                if let (Some(old_inner), Some(new_inner)) = (old_cell, new_cell) {
+                    if old_inner.state != new_inner.state {
+                        changes.push(CellChange {
+                            pos: new_inner.pos,
+                            before: old_inner.state,
+                            after: new_inner.state,
+                        });
+                    }
+                }
+            }
+        }
+
+        Delta { changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn changes(&self) -> &[CellChange] {
+        &self.changes
+    }
+
+    // Moves the board forward by one generation.
+    pub fn apply(&self, board: &mut Board) {
+        for change in self.changes.iter() {
+            set_state(board, change.pos, change.after);
+        }
+    }
+
+    // Moves the board backward by one generation.
+    pub fn revert(&self, board: &mut Board) {
+        for change in self.changes.iter() {
+            set_state(board, change.pos, change.before);
+        }
+    }
+
+    fn size_in_bytes(&self) -> usize {
+        size_of::<Delta>() + self.changes.capacity() * size_of::<CellChange>()
+    }
+}
+
+pub(crate) fn set_state(board: &mut Board, (x, y): (u32, u32), state: CellState) {
+    if let Some(Some(cell)) = board
+        .get_mut(x as usize)
+        .and_then(|column| column.get_mut(y as usize))
+    {
+        cell.state = state;
+    }
+}
+
+// How much history to keep. The oldest generations are dropped once either
+// bound is exceeded.
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryLimit {
+    pub max_generations: usize,
+    pub max_bytes: Option<usize>,
+}
+
+impl Default for HistoryLimit {
+    fn default() -> Self {
+        HistoryLimit {
+            max_generations: DEFAULT_MAX_GENERATIONS,
+            max_bytes: None,
+        }
+    }
+}
+
+// A bounded ring buffer of generation deltas. The cursor points at the
+// generation currently on the board, so stepping back and then forward again
+// replays the stored deltas instead of recomputing them.
+#[derive(Debug, Default)]
+pub struct History {
+    deltas: VecDeque<Delta>,
+    cursor: usize,
+    bytes: usize,
+    limit: HistoryLimit,
+}
+
+impl History {
+    pub fn new(limit: HistoryLimit) -> Self {
+        History {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    pub fn position(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.cursor = 0;
+        self.bytes = 0;
+    }
+
+    // Records a new generation. Anything ahead of the cursor is discarded
+    // since the board has diverged from it.
+    pub fn push(&mut self, delta: Delta) {
+        self.truncate_future();
+        self.bytes += delta.size_in_bytes();
+        self.deltas.push_back(delta);
+        self.cursor = self.deltas.len();
+        self.evict();
+    }
+
+    // Drops every generation ahead of the cursor.
+    pub fn truncate_future(&mut self) {
+        while self.deltas.len() > self.cursor {
+            if let Some(dropped) = self.deltas.pop_back() {
+                self.bytes -= dropped.size_in_bytes();
+            }
+        }
+    }
+
+    pub fn step_back(&mut self, board: &mut Board) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        self.deltas[self.cursor].revert(board);
+
+        true
+    }
+
+    pub fn step_forward(&mut self, board: &mut Board) -> bool {
+        if self.cursor >= self.deltas.len() {
+            return false;
+        }
+        self.deltas[self.cursor].apply(board);
+        self.cursor += 1;
+
+        true
+    }
+
+    // Moves the board to the given position in the history, clamped to the
+    // generations that are still stored. Returns the number of generations
+    // moved, negative when going backwards.
+    pub fn seek(&mut self, board: &mut Board, position: usize) -> isize {
+        let target = position.min(self.deltas.len());
+        let mut moved = 0;
+        while self.cursor > target && self.step_back(board) {
+            moved -= 1;
+        }
+        while self.cursor < target && self.step_forward(board) {
+            moved += 1;
+        }
+
+        moved
+    }
+
+    fn evict(&mut self) {
+        loop {
+            let over_depth = self.deltas.len() > self.limit.max_generations;
+            let over_budget = self
+                .limit
+                .max_bytes
+                .is_some_and(|max_bytes| self.bytes > max_bytes);
+            if !(over_depth || over_budget) {
+                break;
+            }
+            match self.deltas.pop_front() {
+                Some(dropped) => {
+                    self.bytes -= dropped.size_in_bytes();
+                    self.cursor = self.cursor.saturating_sub(1);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::Cell;
+
+    fn board_with(alive: &[(u32, u32)]) -> Board {
+        let mut board = vec![vec![None; 3]; 3];
+        for x in 0..3 {
+            for y in 0..3 {
+                let mut cell = Cell::new(x, y);
+                if alive.contains(&(x, y)) {
+                    cell.reanimate();
+                }
+                board[x as usize][y as usize] = Some(cell);
+            }
+        }
+
+        board
+    }
+
+    fn alive_cells(board: &Board) -> Vec<(u32, u32)> {
+        board
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|cell| cell.state == CellState::Alive)
+            .map(|cell| cell.pos)
+            .collect()
+    }
+
+    #[test]
+    fn steps_back_and_forward_through_generations() {
+        let first = board_with(&[(0, 1), (1, 1), (2, 1)]);
+        let second = board_with(&[(1, 0), (1, 1), (1, 2)]);
+        let mut history = History::new(HistoryLimit::default());
+        history.push(Delta::between(&first, &second));
+
+        let mut board = second.clone();
+        assert!(history.step_back(&mut board));
+        assert_eq!(alive_cells(&board), alive_cells(&first));
+        assert!(!history.step_back(&mut board));
+
+        assert!(history.step_forward(&mut board));
+        assert_eq!(alive_cells(&board), alive_cells(&second));
+    }
+
+    #[test]
+    fn drops_oldest_generations_past_the_limit() {
+        let first = board_with(&[]);
+        let second = board_with(&[(1, 1)]);
+        let mut history = History::new(HistoryLimit {
+            max_generations: 2,
+            max_bytes: None,
+        });
+        for _ in 0..5 {
+            history.push(Delta::between(&first, &second));
+        }
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.position(), 2);
+    }
+}
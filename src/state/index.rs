@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use tracing::{debug, info, warn};
+
+use super::rle::Rle;
+use super::sparse::detect_period;
+
+const CACHE_HEADER: &str = "# rust-of-life pattern index v1";
+// How long a pattern is run looking for a period while indexing.
+const PERIOD_SEARCH_GENERATIONS: u32 = 256;
+// Rule strings Life patterns are written with, which are the only ones the
+// period detection knows how to run.
+const LIFE_RULES: [&str; 3] = ["B3/S23", "b3/s23", "23/3"];
+
+// What is known about a single pattern file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    // Relative to the indexed directory.
+    pub path: PathBuf,
+    pub name: String,
+    pub author: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub population: usize,
+    pub rule: Option<String>,
+    pub period: Option<u32>,
+    // Used to tell whether the cached entry is still current.
+    modified: u64,
+    size: u64,
+}
+
+// An index of every RLE file below a directory, cached on disk so only new or
+// changed files are parsed when the directory is opened again.
+#[derive(Debug, Clone)]
+pub struct PatternIndex {
+    pub root: PathBuf,
+    pub entries: Vec<IndexEntry>,
+}
+
+impl PatternIndex {
+    // Indexes `root`, reusing entries from the cache for unchanged files and
+    // writing the updated cache back.
+    pub fn open(root: &Path) -> io::Result<Self> {
+        let root = root.canonicalize()?;
+        let cache_path = cache_path(&root);
+        let cached: HashMap<PathBuf, IndexEntry> = match fs::read_to_string(&cache_path) {
+            Ok(contents) => parse_cache(&contents)
+                .into_iter()
+                .map(|entry| (entry.path.clone(), entry))
+                .collect(),
+            Err(_) => HashMap::new(),
+        };
+        info!("Indexing {:?} with {} cached entries.", root, cached.len());
+
+        let mut files = Vec::new();
+        collect_rle_files(&root, &mut files)?;
+        let mut entries = Vec::with_capacity(files.len());
+        for file in files {
+            // One unreadable file shouldn't keep the rest from being indexed.
+            let metadata = match fs::metadata(&file) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    warn!("Skipping {:?} while indexing. {error}", file);
+                    continue;
+                }
+            };
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs());
+            let relative = file.strip_prefix(&root).unwrap_or(&file).to_path_buf();
+            match cached.get(&relative) {
+                Some(entry) if entry.modified == modified && entry.size == metadata.len() => {
+                    entries.push(entry.clone());
+                }
+                _ => entries.extend(index_file(&file, relative, modified, metadata.len())),
+            }
+        }
+        entries.sort_by_cached_key(|entry| entry.name.to_lowercase());
+
+        let index = PatternIndex { root, entries };
+        if let Err(error) = index.write_cache(&cache_path) {
+            warn!("Unable to write pattern index cache {:?}. {error}", cache_path);
+        }
+
+        Ok(index)
+    }
+
+    pub fn search(&self, query: &Query) -> Vec<&IndexEntry> {
+        self.entries.iter().filter(|entry| query.matches(entry)).collect()
+    }
+
+    pub fn load(&self, entry: &IndexEntry) -> Option<Rle> {
+        let contents = fs::read_to_string(self.root.join(&entry.path)).ok()?;
+
+        Rle::parse(&contents).ok()
+    }
+
+    fn write_cache(&self, cache_path: &Path) -> io::Result<()> {
+        if let Some(parent) = cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = format!("{CACHE_HEADER}\n");
+        for entry in self.entries.iter() {
+            let fields = [
+                entry.path.to_string_lossy().to_string(),
+                entry.modified.to_string(),
+                entry.size.to_string(),
+                entry.name.clone(),
+                entry.author.clone().unwrap_or_default(),
+                entry.width.to_string(),
+                entry.height.to_string(),
+                entry.population.to_string(),
+                entry.rule.clone().unwrap_or_default(),
+                entry.period.map(|period| period.to_string()).unwrap_or_default(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| field.replace(['\t', '\n'], " ")).collect();
+            contents.push_str(&fields.join("\t"));
+            contents.push('\n');
+        }
+
+        fs::write(cache_path, contents)
+    }
+}
+
+// Where the index of a directory is cached: under the user's cache directory,
+// named after the indexed path.
+fn cache_path(root: &Path) -> PathBuf {
+    let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    let file_name: String = root
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    cache_dir.join("rust-of-life").join(format!("{file_name}.tsv"))
+}
+
+fn parse_cache(contents: &str) -> Vec<IndexEntry> {
+    let mut lines = contents.lines();
+    if lines.next() != Some(CACHE_HEADER) {
+        return Vec::new();
+    }
+
+    lines
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [path, modified, size, name, author, width, height, population, rule, period] = fields[..] else {
+                return None;
+            };
+            let optional = |field: &str| (!field.is_empty()).then(|| field.to_string());
+            Some(IndexEntry {
+                path: PathBuf::from(path),
+                modified: modified.parse().ok()?,
+                size: size.parse().ok()?,
+                name: name.to_string(),
+                author: optional(author),
+                width: width.parse().ok()?,
+                height: height.parse().ok()?,
+                population: population.parse().ok()?,
+                rule: optional(rule),
+                period: period.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+// Finds the RLE files below `dir`. Only `dir` itself has to be readable;
+// entries below it that can't be read are skipped, as are symlinked
+// directories, which may loop back on themselves.
+fn collect_rle_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                warn!("Skipping an entry of {:?} while indexing. {error}", dir);
+                continue;
+            }
+        };
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_type().is_ok_and(|file_type| file_type.is_symlink()) {
+                debug!("Not following the symlinked directory {:?} while indexing.", path);
+            } else if let Err(error) = collect_rle_files(&path, files) {
+                warn!("Skipping {:?} while indexing. {error}", path);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("rle"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn index_file(file: &Path, relative: PathBuf, modified: u64, size: u64) -> Option<IndexEntry> {
+    let contents = fs::read_to_string(file).ok()?;
+    let rle = match Rle::parse(&contents) {
+        Ok(rle) => rle,
+        Err(error) => {
+            debug!("Skipping {:?} while indexing. {error}", file);
+            return None;
+        }
+    };
+    let runs_as_life = rle
+        .rule
+        .as_deref()
+        .is_none_or(|rule| LIFE_RULES.contains(&rule));
+    let period = runs_as_life
+        .then(|| detect_period(&rle.pattern, PERIOD_SEARCH_GENERATIONS))
+        .flatten()
+        .map(|periodicity| periodicity.period);
+    let name = rle.name.clone().unwrap_or_else(|| {
+        relative
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().to_string())
+    });
+
+    Some(IndexEntry {
+        path: relative,
+        name,
+        author: rle.author.clone(),
+        width: rle.pattern.width,
+        height: rle.pattern.height,
+        population: rle.pattern.population(),
+        rule: rle.rule,
+        period,
+        modified,
+        size,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn holds(&self, left: u64, right: u64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => left == right,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Property {
+    Population,
+    Period,
+    Width,
+    Height,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Name(String),
+    Author(String),
+    Rule(String),
+    Compare(Property, Comparison, u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError(String);
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unable to understand `{}` in the search", self.0)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+// A search over the index. Words match the pattern name; `author:` and
+// `rule:` match those fields; `pop`, `period`, `width` and `height` can be
+// compared with `<`, `<=`, `=`, `>=` or `>`, e.g. `glider period=4 pop<10`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let terms = input.split_whitespace().map(parse_term).collect::<Result<_, _>>()?;
+
+        Ok(Query { terms })
+    }
+
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        self.terms.iter().all(|term| match term {
+            Term::Name(word) => entry.name.to_lowercase().contains(word),
+            Term::Author(word) => entry
+                .author
+                .as_ref()
+                .is_some_and(|author| author.to_lowercase().contains(word)),
+            Term::Rule(rule) => entry
+                .rule
+                .as_ref()
+                .is_some_and(|entry_rule| entry_rule.eq_ignore_ascii_case(rule)),
+            Term::Compare(property, comparison, value) => {
+                let actual = match property {
+                    Property::Population => Some(entry.population as u64),
+                    Property::Period => entry.period.map(u64::from),
+                    Property::Width => Some(entry.width as u64),
+                    Property::Height => Some(entry.height as u64),
+                };
+                actual.is_some_and(|actual| comparison.holds(actual, *value))
+            }
+        })
+    }
+}
+
+fn parse_term(word: &str) -> Result<Term, QueryError> {
+    let invalid = || QueryError(word.to_string());
+    if let Some(author) = word.strip_prefix("author:") {
+        return Ok(Term::Author(author.to_lowercase()));
+    }
+    if let Some(rule) = word.strip_prefix("rule:") {
+        return Ok(Term::Rule(rule.to_string()));
+    }
+
+    let Some(operator_start) = word.find(['<', '>', '=']) else {
+        return Ok(Term::Name(word.to_lowercase()));
+    };
+    let (key, rest) = word.split_at(operator_start);
+    let property = match key {
+        "pop" | "population" => Property::Population,
+        "period" | "p" => Property::Period,
+        "width" | "w" => Property::Width,
+        "height" | "h" => Property::Height,
+        _ => return Err(invalid()),
+    };
+    let (comparison, value) = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ]
+    .iter()
+    .find_map(|(operator, comparison)| rest.strip_prefix(operator).map(|value| (*comparison, value)))
+    .ok_or_else(invalid)?;
+
+    Ok(Term::Compare(property, comparison, value.parse().map_err(|_| invalid())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, population: usize, period: Option<u32>) -> IndexEntry {
+        IndexEntry {
+            path: PathBuf::from(format!("{name}.rle")),
+            name: name.to_string(),
+            author: Some("John Conway".to_string()),
+            width: 5,
+            height: 4,
+            population,
+            rule: Some("B3/S23".to_string()),
+            period,
+            modified: 0,
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn queries_match_names_and_properties() {
+        let glider = entry("Glider", 5, Some(4));
+        let pulsar = entry("Pulsar", 48, Some(3));
+        let query = Query::parse("GLI pop<10 period=4").unwrap();
+        assert!(query.matches(&glider));
+        assert!(!query.matches(&pulsar));
+        assert!(Query::parse("author:conway rule:b3/s23 pop>=48").unwrap().matches(&pulsar));
+        assert!(Query::parse("colour>3").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlink_loops() {
+        let dir = std::env::temp_dir().join(format!("rust-of-life-index-walk-{}", std::process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested").join("glider.rle"), "x = 3, y = 3\nbo$2bo$3o!\n").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("nested").join("loop")).unwrap();
+        let mut files = Vec::new();
+        let walked = collect_rle_files(&dir, &mut files);
+        fs::remove_dir_all(&dir).unwrap();
+        walked.unwrap();
+        assert_eq!(files, vec![dir.join("nested").join("glider.rle")]);
+    }
+
+    #[test]
+    fn cache_round_trips() {
+        let index = PatternIndex {
+            root: PathBuf::from("/patterns"),
+            entries: vec![entry("Glider", 5, Some(4)), entry("Acorn", 7, None)],
+        };
+        let path = std::env::temp_dir().join(format!("rust-of-life-index-test-{}.tsv", std::process::id()));
+        index.write_cache(&path).unwrap();
+        let parsed = parse_cache(&fs::read_to_string(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(parsed, index.entries);
+    }
+}
@@ -0,0 +1,200 @@
+use super::cell::CellState;
+use super::game::{is_alive, GameData};
+use super::hensel;
+use super::rule::Rule;
+use super::wolfram::WolframRule;
+
+// Widest neighborhood drawn around an inspected cell.
+const MAX_DRAWN_RANGE: isize = 3;
+
+// Why a cell is in the state it is: everything `Game::tick` looked at when
+// it last worked the cell out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspection {
+    pub pos: (u32, u32),
+    pub state: CellState,
+    pub previous_state: CellState,
+    // Offsets from the cell of the neighbors on the board, with whether each
+    // was alive in the previous generation.
+    pub neighbors: Vec<((isize, isize), bool)>,
+    // How many generations the cell has been in its current state.
+    pub age: u64,
+    pub rule: Rule,
+    // The name of the rule file or one-dimensional rule the board runs,
+    // whose transitions can't be explained like a B/S rule.
+    pub rule_file: Option<String>,
+}
+
+impl Inspection {
+    // Inspects the cell at `pos`, or `None` if it is off the board.
+    pub fn of(game_data: &GameData, (x, y): (u32, u32)) -> Option<Self> {
+        let state = game_data.cells.get(x as usize)?.get(y as usize)?.as_ref()?.state;
+        let previous = &game_data.previous_generation;
+        let previous_state = previous
+            .get(x as usize)
+            .and_then(|column| column.get(y as usize))
+            .and_then(|cell| cell.as_ref())
+            .map_or(CellState::Dead, |cell| cell.state);
+        let size = (
+            previous.len() as isize,
+            previous.first().map_or(0, |column| column.len()) as isize,
+        );
+        let parsed = game_data.rule.parse::<Rule>();
+        let rule_file = parsed.is_err().then(|| game_data.rule.clone());
+        let rule = parsed.unwrap_or_default();
+        let offsets = rule.neighborhood.offsets_at(y as usize);
+        let neighbors = game_data
+            .topology
+            .neighbors((x as usize, y as usize), size, &offsets)
+            .map(|(offset, neighbor)| (offset, is_alive(previous, neighbor)))
+            .collect();
+        let age = game_data
+            .ages
+            .get(x as usize)
+            .and_then(|column| column.get(y as usize))
+            .copied()
+            .unwrap_or(0);
+
+        Some(Inspection {
+            pos: (x, y),
+            state,
+            previous_state,
+            neighbors,
+            age,
+            rule,
+            rule_file,
+        })
+    }
+
+    // Live cells counted by the rule, including the cell itself if its
+    // neighborhood does.
+    pub fn live_neighbors(&self) -> usize {
+        let middle = self.rule.neighborhood.middle && self.previous_state == CellState::Alive;
+
+        self.neighbors.iter().filter(|(_, alive)| *alive).count() + usize::from(middle)
+    }
+
+    // Which of the eight nearest cells were alive, as bits of
+    // `hensel::RING`.
+    pub fn configuration(&self) -> u8 {
+        self.neighbors
+            .iter()
+            .filter(|(_, alive)| *alive)
+            .filter_map(|(offset, _)| hensel::RING.iter().position(|ring| ring == offset))
+            .fold(0, |configuration, bit| configuration | 1 << bit)
+    }
+
+    // The part of the rule that took the cell from its previous state to
+    // its current one. Isotropic rules name the arrangement of neighbors
+    // with its Hensel letter, e.g. B2a. A cell the rule couldn't have put in
+    // its state, after an edit or stepping back, is said to be so.
+    pub fn clause(&self) -> String {
+        if let Some(rule) = self.rule_file.as_ref().filter(|rule| rule.parse::<WolframRule>().is_ok()) {
+            return format!("state {}: rows scroll up, the bottom one following {rule}", self.state.index());
+        }
+        if let Some(rule_file) = &self.rule_file {
+            return format!("state {} follows the transitions of {rule_file}", self.state.index());
+        }
+        let count = match hensel::class_of(self.configuration()) {
+            (count, Some(letter)) if !self.rule.is_totalistic() => format!("{count}{letter}"),
+            _ => self.live_neighbors().to_string(),
+        };
+        let rule = self.rule.to_string();
+        let expected = if self.rule.is_totalistic() {
+            self.rule.next_state(self.previous_state, self.live_neighbors())
+        } else {
+            self.rule.next_state_of_configuration(self.previous_state, self.configuration())
+        };
+        let edited = || format!("edited or rewound: {rule} doesn't give this from the previous generation");
+        if expected != self.state {
+            return edited();
+        }
+        match (self.previous_state, self.state) {
+            (CellState::Dead, CellState::Alive) => format!("born: B{count} is in {rule}"),
+            (CellState::Alive, CellState::Alive) => format!("survived: S{count} is in {rule}"),
+            (CellState::Alive, _) => format!("died: S{count} is not in {rule}"),
+            (CellState::Dead, CellState::Dead) => format!("stayed dead: B{count} is not in {rule}"),
+            (CellState::Dying(_), _) => format!("dying: cells count down whatever their neighbors in {rule}"),
+            (CellState::Dead, CellState::Dying(_)) => edited(),
+        }
+    }
+
+    // The previous generation around the cell: `#` for a live neighbor, `.`
+    // for a dead one and a space past the edge of a bounded board or outside
+    // the neighborhood. Neighborhoods too wide to show are left out.
+    pub fn neighborhood(&self) -> Vec<String> {
+        let range = self.rule.neighborhood.range as isize;
+        if range > MAX_DRAWN_RANGE {
+            return Vec::new();
+        }
+
+        (-range..=range)
+            .map(|delta_y| {
+                (-range..=range)
+                    .map(|delta_x| {
+                        if (delta_x, delta_y) == (0, 0) {
+                            return if self.previous_state == CellState::Alive { '@' } else { 'o' };
+                        }
+                        match self.neighbors.iter().find(|(offset, _)| *offset == (delta_x, delta_y)) {
+                            Some((_, true)) => '#',
+                            Some((_, false)) => '.',
+                            None => ' ',
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+fn state_name(state: CellState) -> &'static str {
+    match state {
+        CellState::Alive => "alive",
+        CellState::Dead => "dead",
+        CellState::Dying(_) => "dying",
+    }
+}
+
+impl std::fmt::Display for Inspection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Cell {}, {}", self.pos.0, self.pos.1)?;
+        writeln!(f, "Now {}, was {}", state_name(self.state), state_name(self.previous_state))?;
+        writeln!(f, "{} live neighbors", self.live_neighbors())?;
+        for row in self.neighborhood() {
+            writeln!(f, "  {row}")?;
+        }
+        writeln!(f, "{}", self.clause())?;
+        write!(f, "In this state for {} generations", self.age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::game::Game;
+    use crate::state::rle::Rle;
+
+    #[test]
+    fn explains_the_middle_of_a_blinker() {
+        let blinker = Rle::parse("x = 3, y = 1\n3o!").unwrap().pattern;
+        let mut game = Game::builder().with_size(5, 5).with_pattern(blinker).build().unwrap();
+        game.tick();
+
+        let end = Inspection::of(game.game_data(), (2, 1)).unwrap();
+        assert_eq!((end.previous_state, end.state), (CellState::Dead, CellState::Alive));
+        assert_eq!(end.live_neighbors(), 3);
+        assert_eq!(end.clause(), "born: B3 is in B3/S23");
+        assert_eq!(end.neighborhood(), vec!["...", ".o.", "###"]);
+
+        game.tick();
+        let middle = Inspection::of(game.game_data(), (2, 2)).unwrap();
+        assert_eq!(middle.clause(), "survived: S2 is in B3/S23");
+        assert_eq!(middle.age, 2);
+        assert!(Inspection::of(game.game_data(), (5, 0)).is_none());
+
+        // An edit isn't anything the rule did.
+        game.set_cell((0, 0), CellState::Alive);
+        let edited = Inspection::of(game.game_data(), (0, 0)).unwrap();
+        assert_eq!(edited.clause(), "edited or rewound: B3/S23 doesn't give this from the previous generation");
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use super::game::Board;
+use super::history::{set_state, CellChange};
+
+const DEFAULT_MAX_BATCHES: usize = 100;
+
+// A group of edits that are undone and redone together, e.g. every cell
+// touched by a single drag-paint stroke.
+#[derive(Debug, Clone, Default)]
+pub struct EditBatch {
+    changes: Vec<CellChange>,
+    // Where in `changes` each cell edited so far is.
+    positions: HashMap<(u32, u32), usize>,
+}
+
+impl EditBatch {
+    // Records a change, folding repeated edits of the same cell into one so
+    // the batch always goes from the state before the stroke to the state
+    // after it.
+    fn record(&mut self, change: CellChange) {
+        match self.positions.get(&change.pos) {
+            Some(index) => self.changes[*index].after = change.after,
+            None => {
+                self.positions.insert(change.pos, self.changes.len());
+                self.changes.push(change);
+            }
+        }
+    }
+
+    // Drops the cells that ended up back in the state they started in.
+    fn finish(&mut self) {
+        self.changes.retain(|change| change.before != change.after);
+        self.positions.clear();
+    }
+
+    fn apply(&self, board: &mut Board) {
+        for change in self.changes.iter() {
+            set_state(board, change.pos, change.after);
+        }
+    }
+
+    fn revert(&self, board: &mut Board) {
+        for change in self.changes.iter() {
+            set_state(board, change.pos, change.before);
+        }
+    }
+}
+
+// Undo/redo stacks for user edits. This is kept apart from the generation
+// history so undoing an edit never rewinds the simulation.
+#[derive(Debug)]
+pub struct EditJournal {
+    undo_stack: Vec<EditBatch>,
+    redo_stack: Vec<EditBatch>,
+    open_batch: Option<EditBatch>,
+    // Batches nest, e.g. a cut is a copy followed by a clear; only the
+    // outermost one becomes an undo unit.
+    depth: usize,
+    max_batches: usize,
+}
+
+impl Default for EditJournal {
+    fn default() -> Self {
+        EditJournal {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            open_batch: None,
+            depth: 0,
+            max_batches: DEFAULT_MAX_BATCHES,
+        }
+    }
+}
+
+impl EditJournal {
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // Starts a batch. Every change recorded until the matching `end_batch`
+    // becomes a single undo unit.
+    pub fn begin_batch(&mut self) {
+        self.depth += 1;
+        self.open_batch.get_or_insert_with(EditBatch::default);
+    }
+
+    pub fn end_batch(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            self.close_batch();
+        }
+    }
+
+    // Commits whatever is open regardless of nesting, e.g. when a stroke
+    // never received its end.
+    pub fn close_batch(&mut self) {
+        self.depth = 0;
+        if let Some(batch) = self.open_batch.take() {
+            self.commit(batch);
+        }
+    }
+
+    // Records a change. Outside of a batch the change is its own undo unit.
+    pub fn record(&mut self, change: CellChange) {
+        match self.open_batch.as_mut() {
+            Some(batch) => batch.record(change),
+            None => {
+                let mut batch = EditBatch::default();
+                batch.record(change);
+                self.commit(batch);
+            }
+        }
+    }
+
+    pub fn undo(&mut self, board: &mut Board) -> bool {
+        self.close_batch();
+        match self.undo_stack.pop() {
+            Some(batch) => {
+                batch.revert(board);
+                self.redo_stack.push(batch);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, board: &mut Board) -> bool {
+        self.close_batch();
+        match self.redo_stack.pop() {
+            Some(batch) => {
+                batch.apply(board);
+                self.undo_stack.push(batch);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.open_batch = None;
+        self.depth = 0;
+    }
+
+    fn commit(&mut self, mut batch: EditBatch) {
+        batch.finish();
+        if batch.changes.is_empty() {
+            return;
+        }
+        self.redo_stack.clear();
+        self.undo_stack.push(batch);
+        if self.undo_stack.len() > self.max_batches {
+            self.undo_stack.remove(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::{Cell, CellState};
+
+    fn empty_board() -> Board {
+        (0..3)
+            .map(|x| (0..3).map(|y| Some(Cell::new(x, y))).collect())
+            .collect()
+    }
+
+    fn paint(board: &mut Board, journal: &mut EditJournal, pos: (u32, u32)) {
+        set_state(board, pos, CellState::Alive);
+        journal.record(CellChange {
+            pos,
+            before: CellState::Dead,
+            after: CellState::Alive,
+        });
+    }
+
+    fn population(board: &Board) -> usize {
+        board
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|cell| cell.state == CellState::Alive)
+            .count()
+    }
+
+    #[test]
+    fn a_stroke_is_undone_as_one_unit() {
+        let mut board = empty_board();
+        let mut journal = EditJournal::default();
+        journal.begin_batch();
+        paint(&mut board, &mut journal, (0, 0));
+        paint(&mut board, &mut journal, (1, 1));
+        journal.end_batch();
+        paint(&mut board, &mut journal, (2, 2));
+
+        assert!(journal.undo(&mut board));
+        assert_eq!(population(&board), 2);
+        assert!(journal.undo(&mut board));
+        assert_eq!(population(&board), 0);
+        assert!(!journal.undo(&mut board));
+
+        assert!(journal.redo(&mut board));
+        assert_eq!(population(&board), 2);
+    }
+
+    #[test]
+    fn a_stroke_ending_where_it_started_is_not_recorded() {
+        let mut board = empty_board();
+        let mut journal = EditJournal::default();
+        journal.begin_batch();
+        paint(&mut board, &mut journal, (0, 0));
+        set_state(&mut board, (0, 0), CellState::Dead);
+        journal.record(CellChange {
+            pos: (0, 0),
+            before: CellState::Alive,
+            after: CellState::Dead,
+        });
+        journal.end_batch();
+
+        assert!(!journal.can_undo());
+    }
+}
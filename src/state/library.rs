@@ -0,0 +1,107 @@
+use std::fmt;
+
+use super::rle::{Rle, RleError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Gun,
+    Puffer,
+    Methuselah,
+}
+
+impl Category {
+    pub const ALL: [Category; 6] = [
+        Category::StillLife,
+        Category::Oscillator,
+        Category::Spaceship,
+        Category::Gun,
+        Category::Puffer,
+        Category::Methuselah,
+    ];
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Category::StillLife => "Still lifes",
+            Category::Oscillator => "Oscillators",
+            Category::Spaceship => "Spaceships",
+            Category::Gun => "Guns",
+            Category::Puffer => "Puffers",
+            Category::Methuselah => "Methuselahs",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// A pattern shipped inside the binary.
+#[derive(Debug, Clone, Copy)]
+pub struct LibraryPattern {
+    pub name: &'static str,
+    pub category: Category,
+    rle: &'static str,
+}
+
+impl LibraryPattern {
+    pub fn load(&self) -> Result<Rle, RleError> {
+        Rle::parse(self.rle)
+    }
+}
+
+macro_rules! library_pattern {
+    ($name:expr, $category:ident, $file:expr) => {
+        LibraryPattern {
+            name: $name,
+            category: Category::$category,
+            rle: include_str!(concat!("../../patterns/", $file)),
+        }
+    };
+}
+
+pub const BUILTIN_PATTERNS: &[LibraryPattern] = &[
+    library_pattern!("Block", StillLife, "block.rle"),
+    library_pattern!("Beehive", StillLife, "beehive.rle"),
+    library_pattern!("Loaf", StillLife, "loaf.rle"),
+    library_pattern!("Boat", StillLife, "boat.rle"),
+    library_pattern!("Ship", StillLife, "ship.rle"),
+    library_pattern!("Tub", StillLife, "tub.rle"),
+    library_pattern!("Pond", StillLife, "pond.rle"),
+    library_pattern!("Blinker", Oscillator, "blinker.rle"),
+    library_pattern!("Toad", Oscillator, "toad.rle"),
+    library_pattern!("Beacon", Oscillator, "beacon.rle"),
+    library_pattern!("Pulsar", Oscillator, "pulsar.rle"),
+    library_pattern!("Pentadecathlon", Oscillator, "pentadecathlon.rle"),
+    library_pattern!("Glider", Spaceship, "glider.rle"),
+    library_pattern!("Lightweight spaceship", Spaceship, "lwss.rle"),
+    library_pattern!("Middleweight spaceship", Spaceship, "mwss.rle"),
+    library_pattern!("Heavyweight spaceship", Spaceship, "hwss.rle"),
+    library_pattern!("Gosper glider gun", Gun, "gosper_glider_gun.rle"),
+    library_pattern!("Simkin glider gun", Gun, "simkin_glider_gun.rle"),
+    library_pattern!("Switch engine (10 cells)", Puffer, "switch_engine_10.rle"),
+    library_pattern!("Switch engine (5x5)", Puffer, "switch_engine_5x5.rle"),
+    library_pattern!("R-pentomino", Methuselah, "r_pentomino.rle"),
+    library_pattern!("Diehard", Methuselah, "diehard.rle"),
+    library_pattern!("Acorn", Methuselah, "acorn.rle"),
+];
+
+pub fn patterns_in(category: Category) -> impl Iterator<Item = &'static LibraryPattern> {
+    BUILTIN_PATTERNS
+        .iter()
+        .filter(move |pattern| pattern.category == category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_builtin_pattern_parses() {
+        for pattern in BUILTIN_PATTERNS {
+            let rle = pattern.load().unwrap_or_else(|error| panic!("{}: {error}", pattern.name));
+            assert!(rle.pattern.population() > 0, "{} is empty", pattern.name);
+        }
+    }
+}
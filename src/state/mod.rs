@@ -1,3 +1,26 @@
+pub mod activity;
+pub mod builder;
 pub mod cell;
+pub mod census;
 pub mod game;
+pub mod hensel;
+pub mod history;
+pub mod index;
+pub mod inspect;
+pub mod journal;
+pub mod library;
+pub mod neighborhood;
+pub mod pattern;
+pub mod period;
+pub mod rle;
+pub mod rule;
+pub mod rule_file;
+#[cfg(feature = "runner")]
+pub mod runner;
+pub mod selection;
 pub mod simulation_controls;
+pub mod soup;
+pub mod sparse;
+pub mod topology;
+pub mod watch;
+pub mod wolfram;
@@ -0,0 +1,268 @@
+use std::fmt;
+
+use super::cell::CellState;
+use super::game::Board;
+use super::topology::Topology;
+
+// Largest range a neighborhood may reach out to.
+pub const MAX_RANGE: u8 = 100;
+
+// Which of the cells within range of a cell are its neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shape {
+    // The whole square around the cell.
+    #[default]
+    Moore,
+    // The diamond of cells at most `range` steps away along the axes.
+    VonNeumann,
+    // The cells within `range + 0.5` of the cell's center.
+    Circular,
+    // The six cells around a cell on a hexagonal grid, stored with odd rows
+    // shifted half a cell right: the two cells above and below are up and
+    // left on even rows, up and right on odd ones. Only range 1.
+    Hexagonal,
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shape::Moore => write!(f, "M"),
+            Shape::VonNeumann => write!(f, "N"),
+            Shape::Circular => write!(f, "C"),
+            Shape::Hexagonal => write!(f, "H"),
+        }
+    }
+}
+
+// The cells counted around each cell: a shape of the given range, and
+// whether the cell itself counts too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Neighborhood {
+    pub shape: Shape,
+    pub range: u8,
+    pub middle: bool,
+}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Neighborhood::moore(1)
+    }
+}
+
+impl Neighborhood {
+    pub fn moore(range: u8) -> Self {
+        Neighborhood {
+            shape: Shape::Moore,
+            range,
+            middle: false,
+        }
+    }
+
+    // The four cells sharing an edge with a cell, the `V` rules.
+    pub fn von_neumann() -> Self {
+        Neighborhood {
+            shape: Shape::VonNeumann,
+            ..Neighborhood::moore(1)
+        }
+    }
+
+    // The six cells around a cell on a hexagonal grid, the `H` rules.
+    pub fn hexagonal() -> Self {
+        Neighborhood {
+            shape: Shape::Hexagonal,
+            ..Neighborhood::moore(1)
+        }
+    }
+
+    // The eight cells around a cell, as used by life-like rules.
+    pub fn is_nearest(&self) -> bool {
+        *self == Neighborhood::default()
+    }
+
+    // The letter written after a B/S rule for this neighborhood, empty for
+    // the eight nearest cells, or `None` if it needs the Larger than Life
+    // notation.
+    pub fn suffix(&self) -> Option<&'static str> {
+        if self.is_nearest() {
+            Some("")
+        } else if *self == Neighborhood::von_neumann() {
+            Some("V")
+        } else if *self == Neighborhood::hexagonal() {
+            Some("H")
+        } else {
+            None
+        }
+    }
+
+    // The leftmost and rightmost offsets reached in the row `delta_y` rows
+    // from a cell in row `y`, or `None` for rows out of range.
+    fn span(&self, y: isize, delta_y: isize) -> Option<(isize, isize)> {
+        let range = self.range as isize;
+        if delta_y.abs() > range {
+            return None;
+        }
+        let half_width = match self.shape {
+            Shape::Moore => range,
+            Shape::VonNeumann => range - delta_y.abs(),
+            Shape::Circular => {
+                let reach = range * range + range - delta_y * delta_y;
+                (reach as f64).sqrt() as isize
+            }
+            Shape::Hexagonal if delta_y == 0 => 1,
+            Shape::Hexagonal if y.rem_euclid(2) == 0 => return Some((-1, 0)),
+            Shape::Hexagonal => return Some((0, 1)),
+        };
+
+        Some((-half_width, half_width))
+    }
+
+    // Offsets of the neighbors from a cell in an even row, row by row,
+    // leaving out the cell itself.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        self.offsets_at(0)
+    }
+
+    // Offsets of the neighbors from a cell in row `y`, which only matters
+    // on a hexagonal grid.
+    pub fn offsets_at(&self, y: usize) -> Vec<(isize, isize)> {
+        let range = self.range as isize;
+        (-range..=range)
+            .filter_map(|delta_y| Some((delta_y, self.span(y as isize, delta_y)?)))
+            .flat_map(|(delta_y, (left, right))| (left..=right).map(move |delta_x| (delta_x, delta_y)))
+            .filter(|offset| *offset != (0, 0))
+            .collect()
+    }
+
+    // The most live cells a cell can count.
+    pub fn size(&self) -> usize {
+        self.offsets().len() + usize::from(self.middle)
+    }
+}
+
+// Live cells in the neighborhood of every cell on the board, indexed
+// `[x][y]` like the board. The board is padded by the range, with wrapped
+// cells on a torus, and summed into a table so each row of a neighborhood
+// is counted in constant time, and a whole Moore neighborhood at once.
+pub fn live_counts(board: &Board, neighborhood: Neighborhood, topology: Topology) -> Vec<Vec<u32>> {
+    let size_x = board.len();
+    let size_y = board.first().map_or(0, |column| column.len());
+    let range = neighborhood.range as usize;
+    let (padded_x, padded_y) = (size_x + 2 * range, size_y + 2 * range);
+    let size = (size_x as isize, size_y as isize);
+
+    // `sums[x][y]` is the number of live cells in the padded board left of
+    // `x` and above `y`.
+    let mut sums = vec![vec![0_u32; padded_y + 1]; padded_x + 1];
+    for x in 0..padded_x {
+        for y in 0..padded_y {
+            let pos = (x as isize - range as isize, y as isize - range as isize);
+            let alive = topology
+                .wrap(pos, size)
+                .and_then(|(x, y)| board[x][y].as_ref())
+                .is_some_and(|cell| cell.state == CellState::Alive);
+            sums[x + 1][y + 1] = u32::from(alive) + sums[x][y + 1] + sums[x + 1][y] - sums[x][y];
+        }
+    }
+    // Live cells in the padded rectangle from `left, top` up to but not
+    // including `right, bottom`.
+    let rectangle = |left: usize, top: usize, right: usize, bottom: usize| {
+        sums[right][bottom] + sums[left][top] - sums[left][bottom] - sums[right][top]
+    };
+
+    let mut counts = vec![vec![0_u32; size_y]; size_x];
+    for (x, column) in counts.iter_mut().enumerate() {
+        for (y, count) in column.iter_mut().enumerate() {
+            // The cell sits at `x + range, y + range` on the padded board.
+            *count = match neighborhood.shape {
+                Shape::Moore => rectangle(x, y, x + 2 * range + 1, y + 2 * range + 1),
+                _ => (-(range as isize)..=range as isize)
+                    .filter_map(|delta_y| Some((delta_y, neighborhood.span(y as isize, delta_y)?)))
+                    .map(|(delta_y, (left, right))| {
+                        let row = (y as isize + range as isize + delta_y) as usize;
+                        let (left, right) = (x as isize + range as isize + left, x as isize + range as isize + right);
+                        rectangle(left as usize, row, right as usize + 1, row + 1)
+                    })
+                    .sum(),
+            };
+            let alive = board[x][y].as_ref().is_some_and(|cell| cell.state == CellState::Alive);
+            if alive && !neighborhood.middle {
+                *count -= 1;
+            }
+        }
+    }
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::Cell;
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    fn random_board(size_x: u32, size_y: u32, seed: u64) -> Board {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..size_x)
+            .map(|x| {
+                (0..size_y)
+                    .map(|y| {
+                        let mut cell = Cell::new(x, y);
+                        if rng.gen_bool(0.4) {
+                            cell.reanimate();
+                        }
+                        Some(cell)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shapes_have_the_usual_sizes() {
+        let shape = |shape, range| Neighborhood {
+            shape,
+            range,
+            middle: false,
+        };
+        assert_eq!(Neighborhood::default().size(), 8);
+        assert_eq!(shape(Shape::Moore, 5).size(), 120);
+        assert_eq!(shape(Shape::VonNeumann, 1).size(), 4);
+        assert_eq!(shape(Shape::VonNeumann, 2).size(), 12);
+        assert_eq!(shape(Shape::Circular, 1).size(), 8);
+        assert_eq!(shape(Shape::Circular, 2).size(), 20);
+        assert_eq!(Neighborhood::hexagonal().size(), 6);
+        assert_eq!(Neighborhood::hexagonal().offsets_at(1), vec![(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn table_counts_match_counting_each_neighbor() {
+        let board = random_board(13, 9, 3);
+        let mut neighborhoods = vec![Neighborhood::hexagonal()];
+        for shape in [Shape::Moore, Shape::VonNeumann, Shape::Circular] {
+            for (range, middle) in [(1, false), (3, true), (6, false)] {
+                neighborhoods.push(Neighborhood { shape, range, middle });
+            }
+        }
+        for topology in [Topology::Bounded, Topology::Torus] {
+            for neighborhood in neighborhoods.iter().copied() {
+                let counts = live_counts(&board, neighborhood, topology);
+                for x in 0..13 {
+                    for y in 0..9 {
+                        let mut expected = neighborhood
+                            .offsets_at(y as usize)
+                            .into_iter()
+                            .filter_map(|(delta_x, delta_y)| topology.wrap((x + delta_x, y + delta_y), (13, 9)))
+                            .filter(|(x, y)| board[*x][*y].as_ref().unwrap().state == CellState::Alive)
+                            .count() as u32;
+                        if neighborhood.middle && board[x as usize][y as usize].as_ref().unwrap().state == CellState::Alive
+                        {
+                            expected += 1;
+                        }
+                        assert_eq!(counts[x as usize][y as usize], expected, "{neighborhood:?} {topology}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::cell::CellState;
+use super::game::Board;
+use super::selection::Selection;
+
+// Geometric transformations that can be applied to a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    RotateClockwise,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+// A rectangular block of cells detached from any board, used for the
+// clipboard and for stamping patterns onto the board.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub width: u32,
+    pub height: u32,
+    // Row-major, `width * height` entries.
+    cells: Vec<CellState>,
+}
+
+impl Pattern {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pattern {
+            width,
+            height,
+            cells: vec![CellState::Dead; width as usize * height as usize],
+        }
+    }
+
+    // Copies the selected region of a board. Positions outside the board are
+    // left dead.
+    pub fn from_board(board: &Board, selection: &Selection) -> Self {
+        let mut pattern = Pattern::new(selection.width(), selection.height());
+        let (left, top) = selection.top_left();
+        for (x, y) in selection.positions() {
+            if let Some(Some(cell)) = board.get(x as usize).and_then(|column| column.get(y as usize)) {
+                pattern.set(x - left, y - top, cell.state);
+            }
+        }
+
+        pattern
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> CellState {
+        if x >= self.width || y >= self.height {
+            return CellState::Dead;
+        }
+
+        self.cells[y as usize * self.width as usize + x as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, state: CellState) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = state;
+        }
+    }
+
+    // Every cell of the pattern with its offset from the top left corner.
+    pub fn cells(&self) -> impl Iterator<Item = ((u32, u32), CellState)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, state)| {
+                let width = self.width as usize;
+                (((index % width) as u32, (index / width) as u32), *state)
+            })
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|state| **state == CellState::Alive).count()
+    }
+
+    pub fn transformed(&self, transform: Transform) -> Self {
+        match transform {
+            Transform::RotateClockwise => {
+                let mut rotated = Pattern::new(self.height, self.width);
+                for ((x, y), state) in self.cells() {
+                    rotated.set(self.height - 1 - y, x, state);
+                }
+                rotated
+            }
+            Transform::FlipHorizontal => {
+                let mut flipped = Pattern::new(self.width, self.height);
+                for ((x, y), state) in self.cells() {
+                    flipped.set(self.width - 1 - x, y, state);
+                }
+                flipped
+            }
+            Transform::FlipVertical => {
+                let mut flipped = Pattern::new(self.width, self.height);
+                for ((x, y), state) in self.cells() {
+                    flipped.set(x, self.height - 1 - y, state);
+                }
+                flipped
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An L shape:
+    // #.
+    // #.
+    // ##
+    fn l_shape() -> Pattern {
+        let mut pattern = Pattern::new(2, 3);
+        for (x, y) in [(0, 0), (0, 1), (0, 2), (1, 2)] {
+            pattern.set(x, y, CellState::Alive);
+        }
+
+        pattern
+    }
+
+    #[test]
+    fn rotating_four_times_is_the_identity() {
+        let pattern = l_shape();
+        let rotated = pattern.transformed(Transform::RotateClockwise);
+        assert_eq!((rotated.width, rotated.height), (3, 2));
+        // ###
+        // #..
+        assert_eq!(rotated.get(0, 0), CellState::Alive);
+        assert_eq!(rotated.get(2, 0), CellState::Alive);
+        assert_eq!(rotated.get(0, 1), CellState::Alive);
+        assert_eq!(rotated.get(1, 1), CellState::Dead);
+
+        let back = rotated
+            .transformed(Transform::RotateClockwise)
+            .transformed(Transform::RotateClockwise)
+            .transformed(Transform::RotateClockwise);
+        assert_eq!(back, pattern);
+    }
+
+    #[test]
+    fn flips_mirror_the_pattern() {
+        let pattern = l_shape();
+        let horizontal = pattern.transformed(Transform::FlipHorizontal);
+        assert_eq!(horizontal.get(1, 0), CellState::Alive);
+        assert_eq!(horizontal.get(0, 2), CellState::Alive);
+        assert_eq!(horizontal.get(0, 0), CellState::Dead);
+
+        let vertical = pattern.transformed(Transform::FlipVertical);
+        assert_eq!(vertical.get(1, 0), CellState::Alive);
+        assert_eq!(vertical.get(1, 2), CellState::Dead);
+    }
+}
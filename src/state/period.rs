@@ -0,0 +1,231 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use super::cell::CellState;
+use super::game::Board;
+
+// How many generations back a repeat is looked for.
+pub const DEFAULT_CYCLE_WINDOW: usize = 1024;
+
+// The board has returned to an earlier generation and repeats every `period`
+// generations from generation `onset` on, shifted by `displacement` cells.
+// A still life has period 1; only a spaceship on a torus has a displacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardCycle {
+    pub period: u64,
+    pub onset: u64,
+    pub displacement: (i64, i64),
+}
+
+impl BoardCycle {
+    pub fn is_still_life(&self) -> bool {
+        self.period == 1 && self.displacement == (0, 0)
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.displacement != (0, 0)
+    }
+}
+
+// Hash of the cells that aren't dead on a board, equal for boards in the
+// same state.
+pub fn board_hash(board: &Board) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for cell in board.iter().flatten().flatten() {
+        if cell.state != CellState::Dead {
+            cell.pos.hash(&mut hasher);
+            cell.state.index().hash(&mut hasher);
+        }
+    }
+
+    hasher.finish()
+}
+
+// Hash of the cells that aren't dead relative to the top left corner of
+// their bounding box, equal for boards holding the same shape anywhere, and
+// that corner.
+fn shape_hash(board: &Board) -> (u64, (u32, u32)) {
+    let occupied: Vec<((u32, u32), u8)> = board
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|cell| cell.state != CellState::Dead)
+        .map(|cell| (cell.pos, cell.state.index()))
+        .collect();
+    let corner = occupied
+        .iter()
+        .fold(None, |corner: Option<(u32, u32)>, &((x, y), _)| {
+            Some(corner.map_or((x, y), |(min_x, min_y)| (min_x.min(x), min_y.min(y))))
+        })
+        .unwrap_or((0, 0));
+    let mut hasher = DefaultHasher::new();
+    for ((x, y), state) in occupied {
+        (x - corner.0, y - corner.1, state).hash(&mut hasher);
+    }
+
+    (hasher.finish(), corner)
+}
+
+// Shortest signed distance from `from` to `to` around a torus of `size`.
+fn wrapped_offset(from: u32, to: u32, size: usize) -> i64 {
+    let size = size.max(1) as i64;
+    let offset = (to as i64 - from as i64).rem_euclid(size);
+    if offset > size / 2 {
+        offset - size
+    } else {
+        offset
+    }
+}
+
+// Spots the board repeating itself by remembering hashes of the most recent
+// generations. With translations on it also spots the same shape turning up
+// elsewhere, which is only a true cycle on a torus.
+#[derive(Debug, Clone)]
+pub struct CycleDetector {
+    window: usize,
+    translations: bool,
+    boards: HashMap<u64, u64>,
+    shapes: HashMap<u64, (u64, (u32, u32))>,
+    // Generation and hashes of every board remembered, oldest first.
+    recent: VecDeque<(u64, u64, u64)>,
+}
+
+impl Default for CycleDetector {
+    fn default() -> Self {
+        CycleDetector::new(DEFAULT_CYCLE_WINDOW)
+    }
+}
+
+impl CycleDetector {
+    pub fn new(window: usize) -> Self {
+        CycleDetector {
+            window: window.max(1),
+            translations: false,
+            boards: HashMap::new(),
+            shapes: HashMap::new(),
+            recent: VecDeque::new(),
+        }
+    }
+
+    pub fn with_translations(mut self, translations: bool) -> Self {
+        self.translations = translations;
+
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty()
+    }
+
+    // Records the board at `generation`, returning the cycle it is in if the
+    // same board was seen within the window.
+    pub fn observe(&mut self, board: &Board, generation: u64) -> Option<BoardCycle> {
+        let hash = board_hash(board);
+        if let Some(onset) = self.boards.get(&hash).filter(|onset| **onset < generation) {
+            return Some(BoardCycle {
+                period: generation - onset,
+                onset: *onset,
+                displacement: (0, 0),
+            });
+        }
+
+        let (shape, corner) = shape_hash(board);
+        if self.translations {
+            if let Some((onset, earlier)) = self.shapes.get(&shape).filter(|(onset, _)| *onset < generation) {
+                let size_y = board.first().map_or(0, |column| column.len());
+                return Some(BoardCycle {
+                    period: generation - onset,
+                    onset: *onset,
+                    displacement: (
+                        wrapped_offset(earlier.0, corner.0, board.len()),
+                        wrapped_offset(earlier.1, corner.1, size_y),
+                    ),
+                });
+            }
+        }
+
+        self.boards.insert(hash, generation);
+        self.shapes.insert(shape, (generation, corner));
+        self.recent.push_back((generation, hash, shape));
+        while self.recent.len() > self.window {
+            let Some((forgotten, hash, shape)) = self.recent.pop_front() else {
+                break;
+            };
+            if self.boards.get(&hash) == Some(&forgotten) {
+                self.boards.remove(&hash);
+            }
+            if self.shapes.get(&shape).is_some_and(|(generation, _)| *generation == forgotten) {
+                self.shapes.remove(&shape);
+            }
+        }
+
+        None
+    }
+
+    pub fn clear(&mut self) {
+        self.boards.clear();
+        self.shapes.clear();
+        self.recent.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::cell::Cell;
+
+    fn board_with(alive: &[(u32, u32)]) -> Board {
+        let mut board = vec![vec![None; 5]; 5];
+        for (x, column) in board.iter_mut().enumerate() {
+            for (y, slot) in column.iter_mut().enumerate() {
+                let mut cell = Cell::new(x as u32, y as u32);
+                if alive.contains(&(x as u32, y as u32)) {
+                    cell.reanimate();
+                }
+                *slot = Some(cell);
+            }
+        }
+
+        board
+    }
+
+    #[test]
+    fn detects_a_blinker_cycle() {
+        let horizontal = board_with(&[(1, 2), (2, 2), (3, 2)]);
+        let vertical = board_with(&[(2, 1), (2, 2), (2, 3)]);
+        let mut detector = CycleDetector::default();
+
+        assert_eq!(detector.observe(&board_with(&[(0, 0)]), 0), None);
+        assert_eq!(detector.observe(&horizontal, 1), None);
+        assert_eq!(detector.observe(&vertical, 2), None);
+        assert_eq!(
+            detector.observe(&horizontal, 3),
+            Some(BoardCycle {
+                period: 2,
+                onset: 1,
+                displacement: (0, 0)
+            })
+        );
+    }
+
+    #[test]
+    fn detects_translations_across_the_edge() {
+        let mut detector = CycleDetector::default().with_translations(true);
+        assert_eq!(detector.observe(&board_with(&[(3, 0), (4, 0)]), 0), None);
+        assert_eq!(
+            detector.observe(&board_with(&[(0, 0), (1, 0)]), 4).map(|cycle| cycle.displacement),
+            Some((2, 0))
+        );
+    }
+
+    #[test]
+    fn forgets_generations_outside_the_window() {
+        let mut detector = CycleDetector::new(2);
+        let block = board_with(&[(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(detector.observe(&block, 0), None);
+        assert_eq!(detector.observe(&board_with(&[]), 1), None);
+        assert_eq!(detector.observe(&board_with(&[(4, 4)]), 2), None);
+        assert_eq!(detector.observe(&block, 3), None);
+    }
+}
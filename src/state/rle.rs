@@ -0,0 +1,328 @@
+use std::fmt;
+
+use super::cell::CellState;
+use super::pattern::Pattern;
+
+// Longest line written in the body of an RLE file, as recommended by the
+// format description.
+const MAX_LINE_LENGTH: usize = 70;
+// Most cells a pattern may have, as a parsed pattern is held in full.
+const MAX_PATTERN_CELLS: u64 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RleError {
+    MissingHeader,
+    InvalidHeader(String),
+    InvalidToken(char),
+    // The body describes cells outside the size given in the header.
+    OutOfBounds,
+    // The header or a run count is too large to hold.
+    TooLarge,
+}
+
+impl fmt::Display for RleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RleError::MissingHeader => write!(f, "missing `x = .., y = ..` header line"),
+            RleError::InvalidHeader(header) => write!(f, "invalid header line `{header}`"),
+            RleError::InvalidToken(token) => write!(f, "unexpected `{token}` in pattern body"),
+            RleError::OutOfBounds => write!(f, "pattern body is larger than its header"),
+            RleError::TooLarge => write!(f, "pattern is larger than {MAX_PATTERN_CELLS} cells"),
+        }
+    }
+}
+
+impl std::error::Error for RleError {}
+
+// A pattern in the run length encoded format used by most Life software,
+// along with the metadata carried in its `#` lines and header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rle {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub comments: Vec<String>,
+    pub rule: Option<String>,
+    pub pattern: Pattern,
+}
+
+impl Rle {
+    pub fn new(pattern: Pattern) -> Self {
+        Rle {
+            name: None,
+            author: None,
+            comments: Vec::new(),
+            rule: None,
+            pattern,
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Rle, RleError> {
+        let mut name = None;
+        let mut author = None;
+        let mut comments = Vec::new();
+        let mut header = None;
+        let mut lines = input.lines();
+
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                let (kind, text) = comment.split_at(comment.len().min(1));
+                let text = text.trim().to_string();
+                match kind {
+                    "N" => name = Some(text),
+                    "O" => author = Some(text),
+                    "C" | "c" => comments.push(text),
+                    _ => (),
+                }
+                continue;
+            }
+            header = Some(parse_header(line)?);
+            break;
+        }
+
+        let (width, height, rule) = header.ok_or(RleError::MissingHeader)?;
+        if width as u64 * height as u64 > MAX_PATTERN_CELLS {
+            return Err(RleError::TooLarge);
+        }
+        let mut pattern = Pattern::new(width, height);
+        let (mut x, mut y) = (0_u32, 0_u32);
+        let mut run: Option<u32> = None;
+        // The high part of a multi-state cell, e.g. the `p` of `pA`.
+        let mut prefix: Option<u16> = None;
+
+        'body: for line in lines {
+            for token in line.trim().chars() {
+                if let Some(digit) = token.to_digit(10) {
+                    let longer = run.unwrap_or(0).checked_mul(10).and_then(|run| run.checked_add(digit));
+                    run = Some(longer.ok_or(RleError::TooLarge)?);
+                    continue;
+                }
+                if ('p'..='y').contains(&token) {
+                    prefix = Some(token as u16 - 'p' as u16 + 1);
+                    continue;
+                }
+                let count = run.take().unwrap_or(1);
+                match token {
+                    'b' | '.' => x = x.saturating_add(count),
+                    'o' | 'A'..='X' => {
+                        let index = match token {
+                            'o' => 1,
+                            letter => prefix.take().unwrap_or(0) * 24 + (letter as u16 - 'A' as u16 + 1),
+                        };
+                        let state = u8::try_from(index)
+                            .map(CellState::from_index)
+                            .map_err(|_| RleError::InvalidToken(token))?;
+                        if x.checked_add(count).is_none_or(|end| end > width) || y >= height {
+                            return Err(RleError::OutOfBounds);
+                        }
+                        for offset in 0..count {
+                            pattern.set(x + offset, y, state);
+                        }
+                        x += count;
+                    }
+                    '$' => {
+                        y = y.saturating_add(count);
+                        x = 0;
+                    }
+                    '!' => break 'body,
+                    token if token.is_whitespace() => (),
+                    token => return Err(RleError::InvalidToken(token)),
+                }
+            }
+        }
+
+        Ok(Rle {
+            name,
+            author,
+            comments,
+            rule,
+            pattern,
+        })
+    }
+}
+
+// Parses `x = 3, y = 3, rule = B3/S23` into its size and optional rule.
+fn parse_header(line: &str) -> Result<(u32, u32, Option<String>), RleError> {
+    let invalid = || RleError::InvalidHeader(line.to_string());
+    let mut width = None;
+    let mut height = None;
+    let mut rule = None;
+    let mut fields = line;
+    while !fields.is_empty() {
+        let (key, value) = fields.split_once('=').ok_or_else(invalid)?;
+        // The rule comes last and may have commas of its own, as Larger than
+        // Life and totalistic one-dimensional rules do.
+        if key.trim() == "rule" {
+            rule = Some(value.trim().to_string());
+            break;
+        }
+        let (value, rest) = value.split_once(',').unwrap_or((value, ""));
+        let value = value.trim();
+        match key.trim() {
+            "x" => width = Some(value.parse::<u32>().map_err(|_| invalid())?),
+            "y" => height = Some(value.parse::<u32>().map_err(|_| invalid())?),
+            _ => (),
+        }
+        fields = rest;
+    }
+
+    Ok((width.ok_or_else(invalid)?, height.ok_or_else(invalid)?, rule))
+}
+
+impl fmt::Display for Rle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name.as_ref() {
+            writeln!(f, "#N {name}")?;
+        }
+        if let Some(author) = self.author.as_ref() {
+            writeln!(f, "#O {author}")?;
+        }
+        for comment in self.comments.iter() {
+            writeln!(f, "#C {comment}")?;
+        }
+        write!(f, "x = {}, y = {}", self.pattern.width, self.pattern.height)?;
+        if let Some(rule) = self.rule.as_ref() {
+            write!(f, ", rule = {rule}")?;
+        }
+        writeln!(f)?;
+
+        let mut line = String::new();
+        for token in body_tokens(&self.pattern) {
+            if line.len() + token.len() > MAX_LINE_LENGTH {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+// The letters of a state in the multi-state encoding: `A` to `X` for states
+// 1 to 24, then `pA` to `pX`, `qA` and so on.
+fn state_letters(index: u8) -> String {
+    let letter = char::from(b'A' + (index - 1) % 24);
+    match (index - 1) / 24 {
+        0 => letter.to_string(),
+        high => format!("{}{letter}", char::from(b'p' + high - 1)),
+    }
+}
+
+// The runs making up the body of the encoding, ending with `!`. Trailing dead
+// cells on a row and trailing empty rows are left out. Patterns with more
+// than two states use the multi-state encoding of `.` and letters.
+fn body_tokens(pattern: &Pattern) -> Vec<String> {
+    let multi_state = pattern.cells().any(|(_, state)| state.index() > 1);
+    let tag = |state: CellState| match (multi_state, state.index()) {
+        (false, 0) => "b".to_string(),
+        (false, _) => "o".to_string(),
+        (true, 0) => ".".to_string(),
+        (true, index) => state_letters(index),
+    };
+    let mut runs: Vec<(u32, String)> = Vec::new();
+    let mut push = |count: u32, tag: String| match runs.last_mut() {
+        Some((last_count, last_tag)) if *last_tag == tag => *last_count += count,
+        _ => runs.push((count, tag)),
+    };
+
+    for y in 0..pattern.height {
+        let mut dead_run = 0;
+        for x in 0..pattern.width {
+            let state = pattern.get(x, y);
+            if state == CellState::Dead {
+                dead_run += 1;
+                continue;
+            }
+            if dead_run > 0 {
+                push(dead_run, tag(CellState::Dead));
+                dead_run = 0;
+            }
+            push(1, tag(state));
+        }
+        if y + 1 < pattern.height {
+            push(1, "$".to_string());
+        }
+    }
+
+    while matches!(runs.last(), Some((_, tag)) if tag == "$") {
+        runs.pop();
+    }
+
+    let mut tokens: Vec<String> = runs
+        .into_iter()
+        .map(|(count, tag)| match count {
+            1 => tag,
+            count => format!("{count}{tag}"),
+        })
+        .collect();
+    tokens.push("!".to_string());
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "#N Glider\n#O Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    #[test]
+    fn parses_header_metadata_and_body() {
+        let rle = Rle::parse(GLIDER).unwrap();
+        assert_eq!(rle.name.as_deref(), Some("Glider"));
+        assert_eq!(rle.author.as_deref(), Some("Richard K. Guy"));
+        assert_eq!(rle.rule.as_deref(), Some("B3/S23"));
+        assert_eq!((rle.pattern.width, rle.pattern.height), (3, 3));
+        assert_eq!(rle.pattern.population(), 5);
+        assert_eq!(rle.pattern.get(1, 0), CellState::Alive);
+        assert_eq!(rle.pattern.get(0, 1), CellState::Dead);
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let rle = Rle::parse(GLIDER).unwrap();
+        assert_eq!(rle.to_string(), GLIDER);
+        assert_eq!(Rle::parse(&rle.to_string()).unwrap(), rle);
+    }
+
+    #[test]
+    fn round_trips_multi_state_cells() {
+        let rle = Rle::parse("x = 4, y = 2, rule = B2/S/C3\n2AB$.pA!\n").unwrap();
+        assert_eq!(rle.pattern.get(1, 0), CellState::Alive);
+        assert_eq!(rle.pattern.get(2, 0), CellState::Dying(1));
+        assert_eq!(rle.pattern.get(1, 1), CellState::Dying(24));
+        assert_eq!(rle.pattern.population(), 2);
+        assert_eq!(rle.to_string(), "x = 4, y = 2, rule = B2/S/C3\n2AB$.pA!\n");
+    }
+
+    #[test]
+    fn rejects_patterns_too_large_to_hold() {
+        assert_eq!(Rle::parse("x = 100000, y = 100000\no!"), Err(RleError::TooLarge));
+        assert_eq!(Rle::parse("x = 2, y = 1\n99999999999o!"), Err(RleError::TooLarge));
+    }
+
+    #[test]
+    fn rejects_runs_past_the_header_size() {
+        assert_eq!(Rle::parse("x = 2, y = 1\n4294967295b2o!"), Err(RleError::OutOfBounds));
+    }
+
+    #[test]
+    fn keeps_commas_in_the_rule() {
+        let rle = Rle::parse("x = 3, y = 1, rule = T1599,K3,R1\nABA!\n").unwrap();
+        assert_eq!(rle.rule.as_deref(), Some("T1599,K3,R1"));
+    }
+
+    #[test]
+    fn rejects_states_past_255() {
+        assert_eq!(Rle::parse("x = 1, y = 1\nyX!"), Err(RleError::InvalidToken('X')));
+    }
+
+    #[test]
+    fn rejects_bodies_without_a_header() {
+        assert_eq!(Rle::parse("bob$2bo$3o!"), Err(RleError::InvalidHeader("bob$2bo$3o!".to_string())));
+        assert_eq!(Rle::parse("#N Nothing"), Err(RleError::MissingHeader));
+    }
+}
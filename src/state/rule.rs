@@ -0,0 +1,528 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::cell::CellState;
+use super::hensel;
+use super::neighborhood::{Neighborhood, Shape, MAX_RANGE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleError(String);
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid rule `{}`, expected e.g. B3/S23, B2-a/S12, B2/S34H, B2/S/C3 or R5,C0,M1,S34..58,B34..45,NM",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+// The numbers of live neighbors a cell reacts to: any of the counts 0 to 15
+// for rules written as B/S digits, a range for Larger than Life, or, for
+// isotropic non-totalistic rules, a set of the 256 ways the eight nearest
+// cells can be alive, as bits of `hensel::RING`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counts {
+    Digits(u16),
+    Range(u16, u16),
+    Configurations([u64; 4]),
+}
+
+impl Counts {
+    pub fn from_digits(counts: &[usize]) -> Self {
+        Counts::Digits(
+            counts
+                .iter()
+                .filter(|count| **count <= 8)
+                .fold(0, |digits, count| digits | 1 << count),
+        )
+    }
+
+    // A set of configurations, written as digits when it holds every
+    // configuration of each count it has any of.
+    pub fn from_configurations(configurations: [u64; 4]) -> Self {
+        let has = |configuration: u8| Counts::Configurations(configurations).contains_configuration(configuration);
+        let digits = (0..=255_u8)
+            .filter(|configuration| has(*configuration))
+            .fold(0_u16, |digits, configuration| digits | 1 << configuration.count_ones());
+        if (0..=255_u8).all(|configuration| has(configuration) == (digits & 1 << configuration.count_ones() != 0)) {
+            Counts::Digits(digits)
+        } else {
+            Counts::Configurations(configurations)
+        }
+    }
+
+    // Whether any way of having `count` live neighbors is included.
+    pub fn contains(&self, count: usize) -> bool {
+        match self {
+            Counts::Digits(digits) => count < 16 && digits & 1 << count != 0,
+            Counts::Range(low, high) => (*low as usize..=*high as usize).contains(&count),
+            Counts::Configurations(_) => (0..=255_u8).any(|configuration| {
+                configuration.count_ones() as usize == count && self.contains_configuration(configuration)
+            }),
+        }
+    }
+
+    // Whether the eight nearest cells alive as in `configuration` are
+    // included. This is a single bit lookup for isotropic rules.
+    pub fn contains_configuration(&self, configuration: u8) -> bool {
+        match self {
+            Counts::Configurations(configurations) => {
+                configurations[configuration as usize / 64] & 1 << (configuration % 64) != 0
+            }
+            _ => self.contains(configuration.count_ones() as usize),
+        }
+    }
+
+    // The lowest and highest count, as written in Larger than Life rules.
+    fn bounds(&self) -> (u16, u16) {
+        match self {
+            Counts::Digits(digits) if *digits == 0 => (1, 0),
+            Counts::Digits(digits) => (digits.trailing_zeros() as u16, 15 - digits.leading_zeros() as u16),
+            Counts::Range(low, high) => (*low, *high),
+            Counts::Configurations(_) => {
+                let counts: Vec<usize> = (0..=8).filter(|count| self.contains(*count)).collect();
+                Counts::from_digits(&counts).bounds()
+            }
+        }
+    }
+}
+
+// Whether a dead cell is born, or a live cell survives, for each possible
+// number of live cells in its neighborhood. Life-like rules count the eight
+// nearest cells, and isotropic ones also look at how those cells are
+// arranged; Larger than Life rules count a wider neighborhood. With more
+// than two `states` it is a Generations rule, where a cell that does not
+// survive spends `states - 2` generations dying before it is dead and can
+// be born again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub birth: Counts,
+    pub survival: Counts,
+    pub states: u8,
+    pub neighborhood: Neighborhood,
+}
+
+impl Rule {
+    // Conway's Game of Life, B3/S23.
+    pub fn conway() -> Self {
+        Rule::from_counts(&[3], &[2, 3])
+    }
+
+    pub fn from_counts(birth: &[usize], survival: &[usize]) -> Self {
+        Rule {
+            birth: Counts::from_digits(birth),
+            survival: Counts::from_digits(survival),
+            states: 2,
+            neighborhood: Neighborhood::default(),
+        }
+    }
+
+    // A Larger than Life rule, e.g. Bosco's Rule is born with 34 to 45 and
+    // survives with 34 to 58 live cells in a range 5 Moore neighborhood
+    // counting the cell itself. One that turns out to be life-like, or a
+    // von Neumann `V` rule, is written as one.
+    pub fn larger_than_life(
+        neighborhood: Neighborhood,
+        birth: (u16, u16),
+        survival: (u16, u16),
+        states: u8,
+    ) -> Self {
+        let counts = |(low, high): (u16, u16)| {
+            if neighborhood.suffix().is_some() && high <= 8 {
+                Counts::from_digits(&(low as usize..=high as usize).collect::<Vec<_>>())
+            } else {
+                Counts::Range(low, high)
+            }
+        };
+
+        Rule {
+            birth: counts(birth),
+            survival: counts(survival),
+            states: states.max(2),
+            neighborhood,
+        }
+    }
+
+    // A Generations rule, e.g. Brian's Brain is `generations(&[2], &[], 3)`.
+    pub fn generations(birth: &[usize], survival: &[usize], states: u8) -> Self {
+        Rule {
+            states: states.max(2),
+            ..Rule::from_counts(birth, survival)
+        }
+    }
+
+    pub fn is_generations(&self) -> bool {
+        self.states > 2
+    }
+
+    // Whether only the number of live neighbors matters, not where they are.
+    pub fn is_totalistic(&self) -> bool {
+        ![self.birth, self.survival]
+            .iter()
+            .any(|counts| matches!(counts, Counts::Configurations(_)))
+    }
+
+    // Whether a live cell stays alive, or a dead one is born, given which of
+    // the eight nearest cells are alive, ignoring the dying states of a
+    // Generations rule.
+    pub fn next_state_alive(&self, alive: bool, configuration: u8) -> bool {
+        if alive {
+            self.survival.contains_configuration(configuration)
+        } else {
+            self.birth.contains_configuration(configuration)
+        }
+    }
+
+    // The state a cell moves to given how many of its neighbors are alive.
+    // Dying cells only count down, whatever their neighbors.
+    pub fn next_state(&self, state: CellState, live_neighbors: usize) -> CellState {
+        self.next_state_where(state, |counts| counts.contains(live_neighbors))
+    }
+
+    // The state a cell moves to given which of the eight nearest cells are
+    // alive, for isotropic rules.
+    pub fn next_state_of_configuration(&self, state: CellState, configuration: u8) -> CellState {
+        self.next_state_where(state, |counts| counts.contains_configuration(configuration))
+    }
+
+    fn next_state_where(&self, state: CellState, includes: impl Fn(&Counts) -> bool) -> CellState {
+        match state {
+            CellState::Alive if includes(&self.survival) => CellState::Alive,
+            CellState::Alive => self.dying_after(0),
+            CellState::Dead if includes(&self.birth) => CellState::Alive,
+            CellState::Dead => CellState::Dead,
+            CellState::Dying(age) => self.dying_after(age),
+        }
+    }
+
+    // The state after a cell `age` generations into dying, which is dead
+    // once it has been through every dying state.
+    fn dying_after(&self, age: u8) -> CellState {
+        if (age as usize) + 2 < self.states as usize {
+            CellState::Dying(age + 1)
+        } else {
+            CellState::Dead
+        }
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+// Parses the birth or survival counts of a rule: digits, each followed by
+// the Hensel letters of the arrangements of neighbors it is limited to
+// (`2a`) or a `-` and the letters it leaves out (`2-a`).
+fn parse_counts(counts: &str, rule: &str) -> Result<Counts, RuleError> {
+    let invalid = || RuleError(rule.to_string());
+    let mut configurations = [0_u64; 4];
+    let mut chars = counts.chars().peekable();
+    while let Some(digit) = chars.next() {
+        let count = digit.to_digit(10).filter(|count| *count <= 8).ok_or_else(invalid)? as u8;
+        let excluded = chars.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = chars.next_if(|letter| letter.is_ascii_alphabetic()) {
+            if !hensel::letters(count).contains(letter) || letters.contains(letter) {
+                return Err(invalid());
+            }
+            letters.push(letter);
+        }
+        if excluded && letters.is_empty() {
+            return Err(invalid());
+        }
+
+        for configuration in 0..=255_u8 {
+            let (class, letter) = hensel::class_of(configuration);
+            let named = letter.is_some_and(|letter| letters.contains(letter));
+            if class == count && (letters.is_empty() || named != excluded) {
+                configurations[configuration as usize / 64] |= 1 << (configuration % 64);
+            }
+        }
+    }
+
+    Ok(Counts::from_configurations(configurations))
+}
+
+// Writes counts as digits with Hensel letters where only some arrangements
+// of a count are included, choosing whichever of `2a` and `2-a` is shorter.
+fn write_counts(counts: &Counts) -> String {
+    (0..=8_u8)
+        .map(|count| {
+            let letters = hensel::letters(count);
+            let (named, unnamed): (String, String) = letters.chars().partition(|letter| {
+                hensel::configurations(count, *letter).all(|configuration| counts.contains_configuration(configuration))
+            });
+            if letters.is_empty() || unnamed.is_empty() || named.is_empty() {
+                return if counts.contains(count as usize) { count.to_string() } else { String::new() };
+            }
+            if named.len() <= unnamed.len() {
+                format!("{count}{named}")
+            } else {
+                format!("{count}-{unnamed}")
+            }
+        })
+        .collect()
+}
+
+// Splits the number of states off a Generations rule, written either as a
+// trailing `C` (`B2/S/C3`, `b2sc3`) or as a third number (`/2/3`), leaving
+// the life-like part. A `C` after a count is a Hensel letter instead.
+fn split_states(lower: &str, rule: &str) -> Result<(String, u8), RuleError> {
+    let invalid = || RuleError(rule.to_string());
+    let parse_states = |states: &str| {
+        states
+            .parse::<u8>()
+            .ok()
+            .filter(|states| *states >= 2)
+            .ok_or_else(invalid)
+    };
+
+    if let Some((life_like, states)) = lower.rsplit_once('c') {
+        if life_like.ends_with(['/', 's']) && !states.is_empty() && states.chars().all(|digit| digit.is_ascii_digit()) {
+            return Ok((life_like.trim_end_matches('/').to_string(), parse_states(states)?));
+        }
+    }
+    let parts: Vec<&str> = lower.split('/').collect();
+    if let [survival, birth, states] = parts.as_slice() {
+        if !lower.starts_with(['b', 's']) {
+            return Ok((format!("{survival}/{birth}"), parse_states(states)?));
+        }
+    }
+
+    Ok((lower.to_string(), 2))
+}
+
+// Parses a count or range of counts, `34` or `34..58`.
+fn parse_range(range: &str) -> Option<(u16, u16)> {
+    let (low, high) = range.split_once("..").unwrap_or((range, range));
+    let (low, high) = (low.parse::<u16>().ok()?, high.parse::<u16>().ok()?);
+
+    (low <= high).then_some((low, high))
+}
+
+// Parses the Larger than Life notation `R5,C0,M1,S34..58,B34..45,NM`: the
+// range, number of states (0 or 2 for two), whether the middle cell counts,
+// the survival and birth ranges and the neighborhood shape (`NM` Moore,
+// `NN` von Neumann, `NC` circular; Moore if left out).
+fn parse_larger_than_life(lower: &str, s: &str) -> Result<Rule, RuleError> {
+    let invalid = || RuleError(s.to_string());
+    let (mut range, mut states, mut middle, mut survival, mut birth) = (None, 2, false, None, None);
+    let mut shape = Shape::Moore;
+    for field in lower.split(',').map(|field| field.trim()) {
+        let (key, value) = field.split_at(field.len().min(1));
+        match key {
+            "r" => range = value.parse::<u8>().ok().filter(|range| (1..=MAX_RANGE).contains(range)),
+            "c" => states = value.parse::<u8>().map_err(|_| invalid())?.max(2),
+            "m" => {
+                middle = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(invalid()),
+                }
+            }
+            "s" => survival = parse_range(value),
+            "b" => birth = parse_range(value),
+            "n" => {
+                shape = match value {
+                    "m" => Shape::Moore,
+                    "n" => Shape::VonNeumann,
+                    "c" => Shape::Circular,
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    let neighborhood = Neighborhood {
+        shape,
+        range: range.ok_or_else(invalid)?,
+        middle,
+    };
+
+    Ok(Rule::larger_than_life(
+        neighborhood,
+        birth.ok_or_else(invalid)?,
+        survival.ok_or_else(invalid)?,
+        states,
+    ))
+}
+
+// Accepts `B3/S23`, `b3s23` and the older survival-first `23/3` notation,
+// each with Hensel letters for isotropic rules (`B2-a/S12`), the Generations
+// forms `B2/S/C3` and `/2/3`, a trailing `V` or `H` for von Neumann and
+// hexagonal neighborhoods (`B2/S34H`), and Larger than Life rules.
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RuleError(s.to_string());
+        let lower = s.trim().to_ascii_lowercase();
+        if lower.starts_with('r') {
+            return parse_larger_than_life(&lower, s);
+        }
+        let (lower, neighborhood) = match lower.strip_suffix(['v', 'h']) {
+            Some(rest) if lower.ends_with('v') => (rest.to_string(), Neighborhood::von_neumann()),
+            Some(rest) => (rest.to_string(), Neighborhood::hexagonal()),
+            None => (lower, Neighborhood::default()),
+        };
+        let (lower, states) = split_states(&lower, s)?;
+        let rule = Rule {
+            states,
+            neighborhood,
+            ..parse_life_like(&lower, s)?
+        };
+        // Hensel letters only describe the eight nearest cells.
+        if !neighborhood.is_nearest() && !rule.is_totalistic() {
+            return Err(invalid());
+        }
+        let most = neighborhood.size();
+        if [rule.birth, rule.survival].iter().any(|counts| (most + 1..=8).any(|count| counts.contains(count))) {
+            return Err(invalid());
+        }
+
+        Ok(rule)
+    }
+}
+
+fn parse_life_like(lower: &str, s: &str) -> Result<Rule, RuleError> {
+    let invalid = || RuleError(s.to_string());
+    let (birth, survival) = if let Some(rest) = lower.strip_prefix('b') {
+        rest.split_once('s')
+            .map(|(birth, survival)| (birth.trim_end_matches('/'), survival))
+            .ok_or_else(invalid)?
+    } else if let Some(rest) = lower.strip_prefix('s') {
+        rest.split_once('b')
+            .map(|(survival, birth)| (birth, survival.trim_end_matches('/')))
+            .ok_or_else(invalid)?
+    } else {
+        lower
+            .split_once('/')
+            .map(|(survival, birth)| (birth, survival))
+            .ok_or_else(invalid)?
+    };
+
+    Ok(Rule {
+        birth: parse_counts(birth, s)?,
+        survival: parse_counts(survival, s)?,
+        ..Rule::default()
+    })
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(suffix) = self.neighborhood.suffix() else {
+            let ((birth_low, birth_high), (survival_low, survival_high)) = (self.birth.bounds(), self.survival.bounds());
+            let states = if self.is_generations() { self.states } else { 0 };
+            return write!(
+                f,
+                "R{},C{states},M{},S{survival_low}..{survival_high},B{birth_low}..{birth_high},N{}",
+                self.neighborhood.range,
+                u8::from(self.neighborhood.middle),
+                self.neighborhood.shape
+            );
+        };
+
+        write!(f, "B{}/S{}", write_counts(&self.birth), write_counts(&self.survival))?;
+        if self.is_generations() {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        write!(f, "{suffix}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_notation() {
+        let conway = Rule::conway();
+        assert_eq!("B3/S23".parse::<Rule>(), Ok(conway));
+        assert_eq!("b3s23".parse::<Rule>(), Ok(conway));
+        assert_eq!("S23/B3".parse::<Rule>(), Ok(conway));
+        assert_eq!("23/3".parse::<Rule>(), Ok(conway));
+        assert_eq!("B36/S23".parse::<Rule>().unwrap().to_string(), "B36/S23");
+        assert_eq!("B2/S".parse::<Rule>().unwrap().to_string(), "B2/S");
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("life".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_generations_rules() {
+        let brians_brain = Rule::generations(&[2], &[], 3);
+        assert_eq!("/2/3".parse::<Rule>(), Ok(brians_brain));
+        assert_eq!("B2/S/C3".parse::<Rule>(), Ok(brians_brain));
+        assert_eq!("b2s/c3".parse::<Rule>(), Ok(brians_brain));
+        assert_eq!("b2sc3".parse::<Rule>(), Ok(brians_brain));
+        assert_eq!("345/2/4".parse::<Rule>().unwrap().to_string(), "B2/S345/C4");
+        assert_eq!("B3/S23/C2".parse::<Rule>(), Ok(Rule::conway()));
+        assert!("B2/S/C1".parse::<Rule>().is_err());
+        assert!("1/2/3/4".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_larger_than_life_rules() {
+        let bosco: Rule = "R5,C0,M1,S34..58,B34..45,NM".parse().unwrap();
+        assert_eq!(bosco.neighborhood.range, 5);
+        assert!(bosco.neighborhood.middle);
+        assert!(bosco.survival.contains(58) && !bosco.survival.contains(59));
+        assert!(bosco.birth.contains(34) && !bosco.birth.contains(33));
+        assert_eq!(bosco.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(
+            "r2,c3,m0,s2..4,b3,nc".parse::<Rule>().unwrap().to_string(),
+            "R2,C3,M0,S2..4,B3..3,NC"
+        );
+        assert_eq!("R1,C0,M0,S2..3,B3..3,NM".parse::<Rule>(), Ok(Rule::conway()));
+        assert!("R5,C0,M1,S34..58".parse::<Rule>().is_err());
+        assert!("R0,C0,M0,S1,B1".parse::<Rule>().is_err());
+        assert!("R5,C0,M2,S1,B1".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_isotropic_rules() {
+        let rule: Rule = "B2-a/S12".parse().unwrap();
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.to_string(), "B2-a/S12");
+        // N and NE are adjacent, N and S are not.
+        assert!(!rule.next_state_alive(false, 0b0000_0011));
+        assert!(rule.next_state_alive(false, 0b0001_0001));
+        assert_eq!(
+            "b3-cekainyqj4cn/s2cekai3-q".parse::<Rule>().unwrap().to_string(),
+            "B3r4cn/S2-n3-q"
+        );
+        assert_eq!("B3cekainyqjr/S23".parse::<Rule>(), Ok(Rule::conway()));
+        assert_eq!("B2a/S/C3".parse::<Rule>().unwrap().to_string(), "B2a/S/C3");
+        assert!("B2x/S23".parse::<Rule>().is_err());
+        assert!("B1k/S23".parse::<Rule>().is_err());
+        assert!("B2-/S23".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parses_neighborhood_suffixes() {
+        let hex_life: Rule = "B2/S34H".parse().unwrap();
+        assert_eq!(hex_life.neighborhood, Neighborhood::hexagonal());
+        assert_eq!(hex_life.to_string(), "B2/S34H");
+        assert_eq!("b1s012v".parse::<Rule>().unwrap().neighborhood, Neighborhood::von_neumann());
+        assert_eq!("B2/S/C3V".parse::<Rule>().unwrap().to_string(), "B2/S/C3V");
+        assert_eq!("R1,C0,M0,S0..2,B1..1,NN".parse::<Rule>().unwrap().to_string(), "B1/S012V");
+        assert!("B5/S23V".parse::<Rule>().is_err());
+        assert!("B2a/S34H".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn dying_cells_count_down_to_dead() {
+        let star_wars: Rule = "345/2/4".parse().unwrap();
+        assert_eq!(star_wars.next_state(CellState::Alive, 1), CellState::Dying(1));
+        assert_eq!(star_wars.next_state(CellState::Dying(1), 2), CellState::Dying(2));
+        assert_eq!(star_wars.next_state(CellState::Dying(2), 2), CellState::Dead);
+        assert_eq!(star_wars.next_state(CellState::Dead, 2), CellState::Alive);
+        assert_eq!(Rule::conway().next_state(CellState::Alive, 1), CellState::Dead);
+    }
+}
@@ -0,0 +1,787 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use super::cell::CellState;
+use super::game::Board;
+use super::neighborhood::Shape;
+use super::topology::Topology;
+
+// Golly's default colors for states without an entry in `@COLORS`: live
+// states fade from red to yellow.
+const DEAD_COLOR: (u8, u8, u8) = (48, 48, 48);
+const FIRST_COLOR: (u8, u8, u8) = (255, 0, 0);
+const LAST_COLOR: (u8, u8, u8) = (255, 255, 0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RuleFileError {}
+
+fn error(line: usize, message: impl Into<String>) -> RuleFileError {
+    RuleFileError {
+        line,
+        message: message.into(),
+    }
+}
+
+// The cells a rule file looks at around each cell, in the order its
+// transitions list them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableNeighborhood {
+    // N, NE, E, SE, S, SW, W, NW.
+    Moore,
+    // N, E, S, W.
+    VonNeumann,
+    // Clockwise from the upper right cell, on the same offset grid as `H`
+    // rules: odd rows are shifted half a cell right.
+    Hexagonal,
+    // W, E.
+    OneDimensional,
+}
+
+impl TableNeighborhood {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "Moore" => Some(TableNeighborhood::Moore),
+            "vonNeumann" => Some(TableNeighborhood::VonNeumann),
+            "hexagonal" => Some(TableNeighborhood::Hexagonal),
+            "oneDimensional" => Some(TableNeighborhood::OneDimensional),
+            _ => None,
+        }
+    }
+
+    // The shape of the board's grid, for drawing it.
+    pub fn shape(&self) -> Shape {
+        match self {
+            TableNeighborhood::VonNeumann => Shape::VonNeumann,
+            TableNeighborhood::Hexagonal => Shape::Hexagonal,
+            TableNeighborhood::Moore | TableNeighborhood::OneDimensional => Shape::Moore,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            TableNeighborhood::Moore => 8,
+            TableNeighborhood::VonNeumann => 4,
+            TableNeighborhood::Hexagonal => 6,
+            TableNeighborhood::OneDimensional => 2,
+        }
+    }
+
+    // Offsets of the neighbors of a cell in row `y`, in transition order.
+    pub fn offsets_at(&self, y: usize) -> &'static [(isize, isize)] {
+        match self {
+            TableNeighborhood::Moore => &[(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+            TableNeighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            TableNeighborhood::Hexagonal if y.is_multiple_of(2) => &[(0, -1), (1, 0), (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+            TableNeighborhood::Hexagonal => &[(1, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (0, -1)],
+            TableNeighborhood::OneDimensional => &[(-1, 0), (1, 0)],
+        }
+    }
+
+    // The neighbor orders a symmetry treats as the same, as permutations of
+    // the transition order, or `None` for `permute`, where any order is.
+    fn symmetries(&self, name: &str) -> Option<Option<Vec<Vec<usize>>>> {
+        let size = self.size();
+        let rotations = |step: usize| -> Vec<Vec<usize>> {
+            (0..size)
+                .step_by(step)
+                .map(|turn| (0..size).map(|i| (i + turn) % size).collect())
+                .collect()
+        };
+        let reflected = |permutations: Vec<Vec<usize>>| -> Vec<Vec<usize>> {
+            let mirrored: Vec<Vec<usize>> = permutations
+                .iter()
+                .map(|permutation| permutation.iter().map(|i| permutation[(size - i) % size]).collect())
+                .collect();
+            permutations.into_iter().chain(mirrored).collect()
+        };
+        let identity = vec![(0..size).collect::<Vec<_>>()];
+        let permutations = match (self, name) {
+            (_, "permute") => return Some(None),
+            (_, "none") => identity,
+            (TableNeighborhood::OneDimensional, "reflect") => vec![vec![0, 1], vec![1, 0]],
+            (TableNeighborhood::Moore, "reflect_horizontal") | (TableNeighborhood::VonNeumann, "reflect_horizontal") => {
+                reflected(identity)
+            }
+            (TableNeighborhood::Moore, "rotate4") => rotations(2),
+            (TableNeighborhood::Moore, "rotate4reflect") => reflected(rotations(2)),
+            (TableNeighborhood::Moore, "rotate8") => rotations(1),
+            (TableNeighborhood::Moore, "rotate8reflect") => reflected(rotations(1)),
+            (TableNeighborhood::VonNeumann, "rotate4") => rotations(1),
+            (TableNeighborhood::VonNeumann, "rotate4reflect") => reflected(rotations(1)),
+            (TableNeighborhood::Hexagonal, "rotate2") => rotations(3),
+            (TableNeighborhood::Hexagonal, "rotate3") => rotations(2),
+            (TableNeighborhood::Hexagonal, "rotate6") => rotations(1),
+            (TableNeighborhood::Hexagonal, "rotate6reflect") => reflected(rotations(1)),
+            _ => return None,
+        };
+
+        Some(Some(permutations))
+    }
+}
+
+// A set of states, one bit for each of the 256 possible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct States([u64; 4]);
+
+impl States {
+    fn single(state: u8) -> Self {
+        let mut states = States::default();
+        states.insert(state);
+
+        states
+    }
+
+    fn insert(&mut self, state: u8) {
+        self.0[state as usize / 64] |= 1 << (state % 64);
+    }
+
+    fn contains(&self, state: u8) -> bool {
+        self.0[state as usize / 64] & 1 << (state % 64) != 0
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=255).filter(|state| self.contains(*state))
+    }
+}
+
+// One line of a `@TABLE` with its variables filled in: the states the cell
+// and each neighbor may be in, and the state the cell moves to.
+#[derive(Debug, Clone)]
+struct Transition {
+    center: States,
+    // Every order of the neighbors the symmetries allow.
+    orders: Vec<Vec<States>>,
+    output: u8,
+}
+
+impl Transition {
+    fn matches(&self, center: u8, neighbors: &[u8], permute: bool) -> bool {
+        if !self.center.contains(center) {
+            return false;
+        }
+        if permute {
+            return assign(neighbors, &self.orders[0], &mut vec![false; neighbors.len()]);
+        }
+
+        self.orders
+            .iter()
+            .any(|order| order.iter().zip(neighbors).all(|(states, neighbor)| states.contains(*neighbor)))
+    }
+}
+
+// Whether every neighbor can be given a different one of `slots` that
+// allows its state, for `permute` tables.
+fn assign(neighbors: &[u8], slots: &[States], used: &mut Vec<bool>) -> bool {
+    let Some((neighbor, rest)) = neighbors.split_first() else {
+        return true;
+    };
+    for (slot, states) in slots.iter().enumerate() {
+        if !used[slot] && states.contains(*neighbor) {
+            used[slot] = true;
+            if assign(rest, slots, used) {
+                return true;
+            }
+            used[slot] = false;
+        }
+    }
+
+    false
+}
+
+// A `@` section of a rule file: its name, and its lines with their line
+// numbers, starting with whatever follows the name.
+struct Section<'a> {
+    name: &'a str,
+    lines: Vec<(usize, &'a str)>,
+}
+
+#[derive(Debug, Clone)]
+enum Transitions {
+    // Tried in order, the first that matches wins; a cell none match stays
+    // as it is.
+    Table {
+        transitions: Vec<Transition>,
+        permute: bool,
+    },
+    // Golly's decision tree: each node is picked by the state of one cell,
+    // in the order NW, NE, SW, SE, N, W, E, S and the cell itself (N, W, E,
+    // S for von Neumann), and the last level gives the next state. The root
+    // is the last node.
+    Tree(Vec<Vec<u32>>),
+}
+
+// A multi-state rule loaded from a Golly `.rule` file. Cell states are
+// numbered as in multi-state RLE: 0 dead, 1 alive and 2 onwards `Dying`.
+#[derive(Debug, Clone)]
+pub struct RuleFile {
+    pub name: String,
+    pub states: u16,
+    pub neighborhood: TableNeighborhood,
+    // Colors of each state, from `@COLORS` or Golly's defaults.
+    pub colors: Vec<(u8, u8, u8)>,
+    // What each state stands for, if known; files only number them.
+    pub state_names: Vec<String>,
+    transitions: Transitions,
+    // Next states already worked out, by the cell's and its neighbors'
+    // states packed a byte each.
+    lookup: HashMap<u128, u8>,
+}
+
+impl RuleFile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| format!("unable to read {}: {error}", path.display()))?;
+
+        RuleFile::parse(&contents).map_err(|error| format!("unable to parse {}: {error}", path.display()))
+    }
+
+    // Reads the `@RULE` name, a `@TABLE` or `@TREE`, and `@COLORS` if
+    // present. Other sections, such as `@ICONS`, are skipped.
+    pub fn parse(contents: &str) -> Result<Self, RuleFileError> {
+        let mut sections: Vec<Section> = Vec::new();
+        for (number, line) in contents.lines().enumerate().map(|(number, line)| (number + 1, line)) {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if let Some(header) = line.strip_prefix('@') {
+                let name = header.split_whitespace().next().unwrap_or_default();
+                sections.push(Section {
+                    name,
+                    lines: vec![(number, header[name.len()..].trim())],
+                });
+            } else if !line.is_empty() {
+                match sections.last_mut() {
+                    Some(section) => section.lines.push((number, line)),
+                    None => return Err(error(number, "expected @RULE before anything else")),
+                }
+            }
+        }
+        let section = |name: &str| sections.iter().find(|section| section.name == name);
+
+        let name = match section("RULE") {
+            Some(section) => match section.lines.iter().find(|(_, line)| !line.is_empty()) {
+                Some((_, name)) => name.to_string(),
+                None => return Err(error(section.lines[0].0, "@RULE needs a name")),
+            },
+            None => return Err(error(1, "missing @RULE")),
+        };
+        let (states, neighborhood, transitions) = match (section("TABLE"), section("TREE")) {
+            (Some(table), _) => parse_table(&table.lines)?,
+            (None, Some(tree)) => parse_tree(&tree.lines)?,
+            (None, None) => return Err(error(1, "missing @TABLE or @TREE")),
+        };
+        let colors = match section("COLORS") {
+            Some(colors) => parse_colors(&colors.lines, states)?,
+            None => (0..states).map(|state| default_color(state, states)).collect(),
+        };
+
+        Ok(RuleFile {
+            name,
+            states,
+            neighborhood,
+            colors,
+            state_names: Vec::new(),
+            transitions,
+            lookup: HashMap::new(),
+        })
+    }
+
+    // The state a cell in `center` moves to given its neighbors' states, in
+    // transition order. States the rule doesn't have, e.g. from a pattern
+    // for another rule, count as state 0.
+    pub fn next_state(&mut self, center: u8, neighbors: &[u8]) -> u8 {
+        let known = |state: u8| if u16::from(state) < self.states { state } else { 0 };
+        let center = known(center);
+        let neighbors: Vec<u8> = neighbors.iter().map(|state| known(*state)).collect();
+        let key = neighbors
+            .iter()
+            .fold(center as u128, |key, neighbor| key << 8 | *neighbor as u128);
+        if let Some(next) = self.lookup.get(&key) {
+            return *next;
+        }
+
+        let next = match &self.transitions {
+            Transitions::Table { transitions, permute } => transitions
+                .iter()
+                .find(|transition| transition.matches(center, &neighbors, *permute))
+                .map_or(center, |transition| transition.output),
+            Transitions::Tree(nodes) => {
+                let inputs: &[usize] = match self.neighborhood {
+                    TableNeighborhood::VonNeumann => &[0, 3, 1, 2],
+                    _ => &[7, 1, 5, 3, 0, 6, 2, 4],
+                };
+                let cells = inputs.iter().map(|i| neighbors[*i]).chain([center]);
+                let mut node = nodes.len() - 1;
+                let mut next = center;
+                for (level, state) in cells.enumerate() {
+                    let child = nodes[node][state as usize] as usize;
+                    if level == inputs.len() {
+                        next = child as u8;
+                    } else {
+                        node = child;
+                    }
+                }
+                next
+            }
+        };
+        self.lookup.insert(key, next);
+
+        next
+    }
+
+    // The next state of every cell on the board.
+    pub fn next_states(&mut self, board: &Board, topology: Topology) -> Vec<Vec<CellState>> {
+        let size = (board.len() as isize, board.first().map_or(0, |column| column.len()) as isize);
+        let state = |(x, y): (usize, usize)| board[x][y].as_ref().map_or(0, |cell| cell.state.index());
+        let mut neighbors = Vec::with_capacity(self.neighborhood.size());
+        (0..size.0 as usize)
+            .map(|x| {
+                (0..size.1 as usize)
+                    .map(|y| {
+                        neighbors.clear();
+                        neighbors.extend(self.neighborhood.offsets_at(y).iter().map(|(delta_x, delta_y)| {
+                            topology
+                                .wrap((x as isize + delta_x, y as isize + delta_y), size)
+                                .map_or(0, state)
+                        }));
+                        CellState::from_index(self.next_state(state((x, y)), &neighbors))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+// A rule file shipped inside the binary, with names for its states.
+#[derive(Debug, Clone, Copy)]
+pub struct LibraryRule {
+    pub name: &'static str,
+    pub state_names: &'static [&'static str],
+    source: &'static str,
+}
+
+impl LibraryRule {
+    pub fn load(&self) -> Result<RuleFile, RuleFileError> {
+        let mut rule_file = RuleFile::parse(self.source)?;
+        rule_file.state_names = self.state_names.iter().map(|name| name.to_string()).collect();
+
+        Ok(rule_file)
+    }
+}
+
+pub const BUILTIN_RULES: &[LibraryRule] = &[LibraryRule {
+    name: "Wireworld",
+    state_names: &["empty", "electron head", "electron tail", "wire"],
+    source: include_str!("../../rules/Wireworld.rule"),
+}];
+
+// The built-in rule called `name`, ignoring case.
+pub fn builtin_rule(name: &str) -> Option<&'static LibraryRule> {
+    BUILTIN_RULES.iter().find(|rule| rule.name.eq_ignore_ascii_case(name.trim()))
+}
+
+// The value of a `key:value` setting, e.g. `n_states:3`.
+fn setting<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (found, value) = line.split_once([':', '='])?;
+
+    (found.trim() == key).then(|| value.trim())
+}
+
+fn parse_state(token: &str, states: u16, number: usize) -> Result<u8, RuleFileError> {
+    token
+        .parse::<u8>()
+        .ok()
+        .filter(|state| u16::from(*state) < states)
+        .ok_or_else(|| error(number, format!("`{token}` is not a state of this rule")))
+}
+
+// Splits a transition into its cells at the commas outside braces, or into
+// single digits when written without commas, e.g. `0123`.
+fn split_cells(line: &str) -> Vec<String> {
+    if !line.contains([',', '{']) {
+        return line.chars().filter(|c| !c.is_whitespace()).map(String::from).collect();
+    }
+    let mut cells = vec![String::new()];
+    let mut depth = 0;
+    for c in line.chars() {
+        match c {
+            ',' if depth == 0 => cells.push(String::new()),
+            c => {
+                depth += i32::from(c == '{') - i32::from(c == '}');
+                if let Some(cell) = cells.last_mut() {
+                    cell.push(c);
+                }
+            }
+        }
+    }
+
+    cells.into_iter().map(|cell| cell.trim().to_string()).collect()
+}
+
+// A cell of a transition: a state, a variable, or a set of either in braces.
+fn parse_states(
+    token: &str,
+    variables: &HashMap<String, States>,
+    states: u16,
+    number: usize,
+) -> Result<States, RuleFileError> {
+    if let Some(inner) = token.strip_prefix('{').and_then(|token| token.strip_suffix('}')) {
+        let mut set = States::default();
+        for item in inner.split(',').map(str::trim) {
+            let item = parse_states(item, variables, states, number)?;
+            item.iter().for_each(|state| set.insert(state));
+        }
+        return Ok(set);
+    }
+    match variables.get(token) {
+        Some(set) => Ok(*set),
+        None => Ok(States::single(parse_state(token, states, number)?)),
+    }
+}
+
+fn parse_table(lines: &[(usize, &str)]) -> Result<(u16, TableNeighborhood, Transitions), RuleFileError> {
+    let header = lines.first().map_or(1, |(number, _)| *number);
+    let mut states = None;
+    let mut neighborhood = None;
+    let mut symmetries = None;
+    let mut variables: HashMap<String, States> = HashMap::new();
+    let mut transitions = Vec::new();
+    for (number, line) in lines.iter().skip(1).map(|(number, line)| (*number, *line)) {
+        if let Some(value) = setting(line, "n_states") {
+            let count = value.parse::<u16>().ok().filter(|count| (2..=256).contains(count));
+            states = Some(count.ok_or_else(|| error(number, "n_states must be between 2 and 256"))?);
+            continue;
+        }
+        if let Some(value) = setting(line, "neighborhood") {
+            neighborhood = Some(
+                TableNeighborhood::parse(value).ok_or_else(|| error(number, format!("unknown neighborhood {value}")))?,
+            );
+            continue;
+        }
+        let (Some(states), Some(neighborhood)) = (states, neighborhood) else {
+            return Err(error(number, "n_states and neighborhood must come first"));
+        };
+        if let Some(value) = setting(line, "symmetries") {
+            symmetries = Some(
+                neighborhood
+                    .symmetries(value)
+                    .ok_or_else(|| error(number, format!("unknown symmetries {value}")))?,
+            );
+            continue;
+        }
+        if let Some(declaration) = line.strip_prefix("var ") {
+            let (name, set) = declaration
+                .split_once('=')
+                .ok_or_else(|| error(number, "expected var name={states}"))?;
+            let set = parse_states(set.trim(), &variables, states, number)?;
+            variables.insert(name.trim().to_string(), set);
+            continue;
+        }
+
+        let cells = split_cells(line);
+        if cells.len() != neighborhood.size() + 2 {
+            return Err(error(
+                number,
+                format!("expected {} states in a transition", neighborhood.size() + 2),
+            ));
+        }
+        // A variable used more than once takes the same state everywhere
+        // it appears, so these are expanded into one transition per state.
+        let bound: Vec<&String> = cells
+            .iter()
+            .filter(|cell| variables.contains_key(*cell))
+            .filter(|cell| cells.iter().filter(|other| other == cell).count() > 1)
+            .fold(Vec::new(), |mut bound, cell| {
+                if !bound.contains(&cell) {
+                    bound.push(cell);
+                }
+                bound
+            });
+        let output = &cells[cells.len() - 1];
+        if variables.contains_key(output) && !bound.contains(&output) {
+            return Err(error(number, format!("the output `{output}` must also be an input")));
+        }
+        let mut bindings: Vec<Vec<(&String, u8)>> = vec![Vec::new()];
+        for name in bound.iter() {
+            bindings = bindings
+                .into_iter()
+                .flat_map(|binding| {
+                    variables[*name].iter().map(move |state| {
+                        let mut binding = binding.clone();
+                        binding.push((*name, state));
+                        binding
+                    })
+                })
+                .collect();
+        }
+        // `permute` matches any order, so only the one written is kept.
+        let permutations = match symmetries.clone().or_else(|| neighborhood.symmetries("none")) {
+            Some(Some(permutations)) => permutations,
+            _ => Vec::new(),
+        };
+        for binding in bindings.iter() {
+            transitions.push(transition(&cells, binding, &variables, states, number, &permutations)?);
+        }
+    }
+
+    let states = states.ok_or_else(|| error(header, "missing n_states"))?;
+    let neighborhood = neighborhood.ok_or_else(|| error(header, "missing neighborhood"))?;
+    let permute = matches!(symmetries, Some(None));
+
+    Ok((states, neighborhood, Transitions::Table { transitions, permute }))
+}
+
+// Fills in a transition's cells, with `binding` giving the states of its
+// bound variables, in every order in `permutations`.
+fn transition(
+    cells: &[String],
+    binding: &[(&String, u8)],
+    variables: &HashMap<String, States>,
+    states: u16,
+    number: usize,
+    permutations: &[Vec<usize>],
+) -> Result<Transition, RuleFileError> {
+    let cell = |token: &String| match binding.iter().find(|(name, _)| *name == token) {
+        Some((_, state)) => Ok(States::single(*state)),
+        None => parse_states(token, variables, states, number),
+    };
+    let center = cell(&cells[0])?;
+    let neighbors = cells[1..cells.len() - 1].iter().map(cell).collect::<Result<Vec<_>, _>>()?;
+    let output = cell(&cells[cells.len() - 1])?;
+    let mut outputs = output.iter();
+    let (Some(output), None) = (outputs.next(), outputs.next()) else {
+        return Err(error(number, "the output must be a single state"));
+    };
+    let mut orders: Vec<Vec<States>> = Vec::new();
+    for permutation in permutations {
+        let order: Vec<States> = permutation.iter().map(|i| neighbors[*i]).collect();
+        if !orders.contains(&order) {
+            orders.push(order);
+        }
+    }
+    if orders.is_empty() {
+        orders.push(neighbors);
+    }
+
+    Ok(Transition { center, orders, output })
+}
+
+fn parse_tree(lines: &[(usize, &str)]) -> Result<(u16, TableNeighborhood, Transitions), RuleFileError> {
+    let header = lines.first().map_or(1, |(number, _)| *number);
+    let (mut states, mut neighbors) = (None, None);
+    let mut nodes: Vec<(u32, Vec<u32>)> = Vec::new();
+    for (number, line) in lines.iter().skip(1).map(|(number, line)| (*number, *line)) {
+        if let Some(value) = setting(line, "num_states") {
+            let count = value.parse::<u16>().ok().filter(|count| (2..=256).contains(count));
+            states = Some(count.ok_or_else(|| error(number, "num_states must be between 2 and 256"))?);
+            continue;
+        }
+        if let Some(value) = setting(line, "num_neighbors") {
+            neighbors = Some(match value {
+                "4" => TableNeighborhood::VonNeumann,
+                "8" => TableNeighborhood::Moore,
+                _ => return Err(error(number, "num_neighbors must be 4 or 8")),
+            });
+            continue;
+        }
+        if setting(line, "num_nodes").is_some() {
+            continue;
+        }
+
+        let Some(states) = states else {
+            return Err(error(number, "num_states must come first"));
+        };
+        let numbers = line
+            .split_whitespace()
+            .map(|number| number.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error(number, "expected a level and a child for each state"))?;
+        let Some((level, children)) = numbers.split_first() else {
+            continue;
+        };
+        let valid = children.len() == states as usize
+            && children.iter().all(|child| match level {
+                1 => *child < states as u32,
+                _ => nodes.get(*child as usize).is_some_and(|(below, _)| below + 1 == *level),
+            });
+        if !valid {
+            return Err(error(number, "expected a level and a child for each state"));
+        }
+        nodes.push((*level, children.to_vec()));
+    }
+
+    let states = states.ok_or_else(|| error(header, "missing num_states"))?;
+    let neighborhood = neighbors.ok_or_else(|| error(header, "missing num_neighbors"))?;
+    if nodes.last().map(|(level, _)| *level as usize) != Some(neighborhood.size() + 1) {
+        return Err(error(header, "the last node must be the root of the tree"));
+    }
+
+    Ok((
+        states,
+        neighborhood,
+        Transitions::Tree(nodes.into_iter().map(|(_, children)| children).collect()),
+    ))
+}
+
+// Reads `state r g b` lines, and `r g b r g b` lines giving a gradient
+// across the live states.
+fn parse_colors(lines: &[(usize, &str)], states: u16) -> Result<Vec<(u8, u8, u8)>, RuleFileError> {
+    let mut colors: Vec<(u8, u8, u8)> = (0..states).map(|state| default_color(state, states)).collect();
+    for (number, line) in lines.iter().skip(1).map(|(number, line)| (*number, *line)) {
+        let numbers = line
+            .split_whitespace()
+            .map(|number| number.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error(number, "expected a state and its red, green and blue"))?;
+        match numbers.as_slice() {
+            [state, r, g, b] if u16::from(*state) < states => colors[*state as usize] = (*r, *g, *b),
+            [r1, g1, b1, r2, g2, b2] => {
+                for state in 1..states {
+                    colors[state as usize] = blend((*r1, *g1, *b1), (*r2, *g2, *b2), state, states);
+                }
+            }
+            _ => return Err(error(number, "expected a state and its red, green and blue")),
+        }
+    }
+
+    Ok(colors)
+}
+
+// Golly's color for `state` of a rule with `states` states when it gives
+// none.
+pub fn default_color(state: u16, states: u16) -> (u8, u8, u8) {
+    match state {
+        0 => DEAD_COLOR,
+        state => blend(FIRST_COLOR, LAST_COLOR, state, states),
+    }
+}
+
+// The color of live `state` on a gradient from `first` to `last`.
+fn blend(first: (u8, u8, u8), last: (u8, u8, u8), state: u16, states: u16) -> (u8, u8, u8) {
+    let steps = (states as i32 - 2).max(1);
+    let along = |from: u8, to: u8| (from as i32 + (to as i32 - from as i32) * (state as i32 - 1) / steps) as u8;
+
+    (along(first.0, last.0), along(first.1, last.1), along(first.2, last.2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::game::Game;
+    use crate::state::soup::SoupParams;
+
+    // Conway's Life as a rule table, and as a tree.
+    const LIFE_TABLE: &str = "@RULE LifeTable
+# Born with three, survives with two or three, dies otherwise.
+@TABLE
+n_states:2
+neighborhood:Moore
+symmetries:permute
+var a={0,1}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+0,1,1,1,0,0,0,0,0,1
+1,1,1,0,0,0,0,0,0,1
+1,1,1,1,0,0,0,0,0,1
+1,a,b,c,d,e,f,g,h,0
+@COLORS
+1 0 255 0
+";
+
+    // Each cell takes the state of its NW neighbor, so the board moves
+    // down and right. The tree's root picks by NW, every other level by
+    // nothing.
+    const SHIFT_TREE: &str = "@RULE Shift
+@TREE
+num_states=2
+num_neighbors=8
+num_nodes=17
+1 0 0
+1 1 1
+2 0 0
+2 1 1
+3 2 2
+3 3 3
+4 4 4
+4 5 5
+5 6 6
+5 7 7
+6 8 8
+6 9 9
+7 10 10
+7 11 11
+8 12 12
+8 13 13
+9 14 15
+";
+
+    #[test]
+    fn runs_tables_and_trees() {
+        let mut life = RuleFile::parse(LIFE_TABLE).unwrap();
+        assert_eq!(life.name, "LifeTable");
+        assert_eq!(life.colors, vec![DEAD_COLOR, (0, 255, 0)]);
+        assert_eq!(life.next_state(0, &[0, 1, 0, 0, 1, 0, 1, 0]), 1);
+        assert_eq!(life.next_state(0, &[0, 1, 0, 0, 1, 0, 0, 0]), 0);
+        assert_eq!(life.next_state(1, &[1, 0, 0, 0, 0, 0, 0, 1]), 1);
+        assert_eq!(life.next_state(1, &[1, 1, 1, 1, 0, 0, 0, 0]), 0);
+        let mut shift = RuleFile::parse(SHIFT_TREE).unwrap();
+        assert_eq!(shift.next_state(0, &[0, 0, 0, 0, 0, 0, 0, 1]), 1);
+        assert_eq!(shift.next_state(1, &[1, 1, 1, 1, 1, 1, 1, 0]), 0);
+        // A pattern for another rule may hold states this one lacks.
+        assert_eq!(shift.next_state(2, &[0, 0, 0, 0, 0, 0, 0, 2]), 0);
+        assert_eq!(life.next_state(5, &[0, 1, 0, 0, 1, 0, 1, 0]), 1);
+
+        assert!(RuleFile::parse("@RULE Broken\n@TABLE\nn_states:2\nneighborhood:Moore\n0,1,1\n").is_err());
+
+        // Golly allows up to 256 states, numbered 0 to 255.
+        let table = "@RULE Hex\n@TABLE\nn_states:256\nneighborhood:hexagonal\n255,0,0,0,0,0,0,1\n";
+        let mut hexagonal = RuleFile::parse(table).unwrap();
+        assert_eq!(hexagonal.next_state(255, &[0; 6]), 1);
+        assert_eq!(hexagonal.neighborhood.shape(), Shape::Hexagonal);
+        assert_eq!(Game::empty_board(4, 4).with_rule_file(hexagonal).game_data().shape, Shape::Hexagonal);
+    }
+
+    #[test]
+    fn wireworld_electrons_follow_the_wire() {
+        let (empty, head, tail, wire) = (0, 1, 2, 3);
+        let mut wireworld = builtin_rule("wireworld").unwrap().load().unwrap();
+        assert_eq!(wireworld.state_names[head as usize], "electron head");
+        assert_eq!(wireworld.next_state(head, &[wire; 8]), tail);
+        assert_eq!(wireworld.next_state(tail, &[head; 8]), wire);
+        assert_eq!(wireworld.next_state(wire, &[head, empty, tail, wire, empty, empty, head, empty]), head);
+        assert_eq!(wireworld.next_state(wire, &[head, head, head, empty, empty, empty, empty, empty]), wire);
+        assert_eq!(wireworld.next_state(wire, &[tail, empty, wire, empty, empty, empty, wire, empty]), wire);
+        assert_eq!(wireworld.next_state(empty, &[head; 8]), empty);
+    }
+
+    #[test]
+    fn a_life_table_runs_like_life() {
+        let soup = SoupParams::default();
+        let mut table = Game::empty_board(24, 24)
+            .with_seed(3)
+            .with_rule_file(RuleFile::parse(LIFE_TABLE).unwrap())
+            .with_soup(soup);
+        let mut life = Game::empty_board(24, 24).with_seed(3).with_soup(soup);
+        for _ in 0..10 {
+            table.tick();
+            life.tick();
+            assert!(table.alive_cells().eq(life.alive_cells()));
+        }
+        assert_eq!(table.to_rle().rule.as_deref(), Some("LifeTable"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::watch::Sender;
+
+use super::game::{Game, GameData};
+use super::simulation_controls::ControlMessages;
+
+pub const TICK_RATE_PER_SECOND: f64 = 15.0;
+// How long a jump computes generations before publishing its progress and
+// looking for control messages.
+const JUMP_FRAME_TIME: Duration = Duration::from_millis(100);
+
+// Plays a game on a tokio task: ticks it while it is running, carries out
+// the control messages it receives and publishes the game data for a view.
+#[derive(Debug)]
+pub struct GameRunner {
+    game: Game,
+    sender: Option<Sender<GameData>>,
+    control_rx: Option<Receiver<ControlMessages>>,
+    tick_rate: f64,
+}
+
+impl GameRunner {
+    pub fn new(game: Game) -> Self {
+        GameRunner {
+            game,
+            sender: None,
+            control_rx: None,
+            tick_rate: TICK_RATE_PER_SECOND,
+        }
+    }
+
+    pub fn with_sender(mut self, tx: Sender<GameData>) -> Self {
+        self.sender = Some(tx);
+
+        self
+    }
+
+    pub fn with_control_rx(mut self, rx: Receiver<ControlMessages>) -> Self {
+        self.control_rx = Some(rx);
+
+        self
+    }
+
+    // Generations per second while running.
+    pub fn with_tick_rate(mut self, tick_rate: f64) -> Self {
+        self.tick_rate = tick_rate;
+
+        self
+    }
+
+    pub async fn start(mut self) {
+        if let Some(sender) = self.sender.clone() {
+            let _ = sender.send(self.game.game_data().clone());
+        }
+
+        loop {
+            if let Some(sender) = self.sender.clone() {
+                let _ = sender.send(self.game.game_data().clone());
+            }
+            if self.game.is_jumping() {
+                let started = Instant::now();
+                while self.game.is_jumping() && started.elapsed() < JUMP_FRAME_TIME {
+                    self.game.jump_tick();
+                }
+                tokio::task::yield_now().await;
+            } else if self.game.game_data().running {
+                tracing::debug!("Simulation running");
+                let tick_time: f64 = (1.0 / self.tick_rate) * 1000.0;
+                tokio::time::sleep(Duration::from_millis(tick_time as u64)).await;
+                self.game.tick();
+            }
+
+            if let Some(controls_tx) = self.control_rx.as_mut() {
+                let control_message = controls_tx.try_recv();
+
+                if let Ok(control_message) = control_message {
+                    tracing::info!("Control message received: {:?}", control_message);
+                    self.game.handle_control_message(control_message);
+                }
+            }
+        }
+    }
+}
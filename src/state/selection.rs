@@ -0,0 +1,53 @@
+// A rectangular region of the board between two corners, inclusive. The
+// corners are kept as given so a selection can be extended in any direction
+// from where it was started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    pub anchor: (u32, u32),
+    pub end: (u32, u32),
+}
+
+impl Selection {
+    pub fn new(anchor: (u32, u32), end: (u32, u32)) -> Self {
+        Selection { anchor, end }
+    }
+
+    pub fn top_left(&self) -> (u32, u32) {
+        (self.anchor.0.min(self.end.0), self.anchor.1.min(self.end.1))
+    }
+
+    pub fn bottom_right(&self) -> (u32, u32) {
+        (self.anchor.0.max(self.end.0), self.anchor.1.max(self.end.1))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.bottom_right().0 - self.top_left().0 + 1
+    }
+
+    pub fn height(&self) -> u32 {
+        self.bottom_right().1 - self.top_left().1 + 1
+    }
+
+    pub fn contains(&self, (x, y): (u32, u32)) -> bool {
+        let (left, top) = self.top_left();
+        let (right, bottom) = self.bottom_right();
+
+        (left..=right).contains(&x) && (top..=bottom).contains(&y)
+    }
+
+    // Every position in the selection, column by column.
+    pub fn positions(&self) -> impl Iterator<Item = (u32, u32)> {
+        let (left, top) = self.top_left();
+        let (right, bottom) = self.bottom_right();
+
+        (left..=right).flat_map(move |x| (top..=bottom).map(move |y| (x, y)))
+    }
+
+    // The same top left corner with the width and height swapped, which is
+    // where a rotated copy of the selection ends up.
+    pub fn rotated(&self) -> Self {
+        let (left, top) = self.top_left();
+
+        Selection::new((left, top), (left + self.height() - 1, top + self.width() - 1))
+    }
+}
@@ -1,3 +1,60 @@
+use std::path::PathBuf;
+
+use super::cell::CellState;
+use super::pattern::{Pattern, Transform};
+use super::selection::Selection;
+use super::soup::SoupParams;
+use super::watch::Watch;
+
 pub struct SimulationControls {
     pub ticking: bool,
 }
+
+// Commands a game carries out, see `Game::handle_control_message`. The
+// terminal interface sends them to the game's runner over a channel.
+#[derive(Debug)]
+pub enum ControlMessages {
+    Reset,
+    // Resets with a new random soup, which later resets also use.
+    ResetSoup(SoupParams),
+    Start,
+    Stop,
+    Step,
+    // Advances the given number of generations as fast as possible, see
+    // `Game::jump_tick`, until done or cancelled.
+    Advance(u64),
+    // Jumps to the given generation the same way, if the board hasn't
+    // passed it yet.
+    JumpTo(u64),
+    CancelJump,
+    StepBack,
+    // Moves to the given position on the rewind timeline.
+    SeekHistory(usize),
+    // User edits. Paints between `BeginStroke` and `EndStroke` are undone
+    // as one unit; a paint outside of a stroke is its own unit.
+    BeginStroke,
+    Paint { pos: (u32, u32), state: CellState },
+    EndStroke,
+    Undo,
+    Redo,
+    // Selection and clipboard operations. Each one is a single undo unit.
+    Copy(Selection),
+    Cut(Selection),
+    Paste((u32, u32)),
+    TransformSelection(Selection, Transform),
+    TransformClipboard(Transform),
+    Clear(Selection),
+    FillRandom(Selection, f64),
+    // Starts counting cell activity for the heatmap afresh.
+    ResetActivity,
+    // Adds or removes a condition that pauses the run once met.
+    AddWatch(Watch),
+    RemoveWatch(Watch),
+    // Writes a pattern onto the board with its top left corner at the
+    // given position.
+    Stamp(Pattern, (u32, u32)),
+    // Saves the board, with the seed and generation, to an RLE file and
+    // loads it back.
+    Save(PathBuf),
+    Load(PathBuf),
+}
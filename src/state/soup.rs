@@ -0,0 +1,201 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use super::cell::CellState;
+use super::pattern::Pattern;
+
+pub const DEFAULT_DENSITY: f64 = 0.5;
+
+// Symmetries a soup can be generated with, named as in apgsearch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Symmetry {
+    // No symmetry.
+    #[default]
+    C1,
+    // Unchanged by a half turn.
+    C2,
+    // Unchanged by a quarter turn.
+    C4,
+    // Mirrored left to right.
+    D2,
+    // Mirrored left to right and top to bottom.
+    D4,
+    // Unchanged by any rotation or reflection of the square.
+    D8,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::C1,
+        Symmetry::C2,
+        Symmetry::C4,
+        Symmetry::D2,
+        Symmetry::D4,
+        Symmetry::D8,
+    ];
+
+    // Quarter turns and diagonal reflections only map a square onto itself.
+    fn needs_square(&self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    // The images of a position under every element of the symmetry group, in
+    // a `width` by `height` region.
+    fn orbit(&self, (x, y): (u32, u32), width: u32, height: u32) -> Vec<(u32, u32)> {
+        let mirror_x = width - 1 - x;
+        let mirror_y = height - 1 - y;
+        match self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (mirror_x, mirror_y)],
+            Symmetry::C4 => vec![(x, y), (mirror_y, x), (mirror_x, mirror_y), (y, mirror_x)],
+            Symmetry::D2 => vec![(x, y), (mirror_x, y)],
+            Symmetry::D4 => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y)],
+            Symmetry::D8 => vec![
+                (x, y),
+                (mirror_y, x),
+                (mirror_x, mirror_y),
+                (y, mirror_x),
+                (mirror_x, y),
+                (x, mirror_y),
+                (y, x),
+                (mirror_y, mirror_x),
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Symmetry::ALL
+            .into_iter()
+            .find(|symmetry| symmetry.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown symmetry `{s}`, expected one of C1, C2, C4, D2, D4 or D8"))
+    }
+}
+
+// How to fill the board with a random soup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoupParams {
+    // Chance of each cell starting alive.
+    pub density: f64,
+    // A random seed is used when none is given.
+    pub seed: Option<u64>,
+    // Size of the soup, centered on the board. The whole board when `None`.
+    pub region: Option<(u32, u32)>,
+    pub symmetry: Symmetry,
+}
+
+impl Default for SoupParams {
+    fn default() -> Self {
+        SoupParams {
+            density: DEFAULT_DENSITY,
+            seed: None,
+            region: None,
+            symmetry: Symmetry::C1,
+        }
+    }
+}
+
+// Parses a region written as `16x16`.
+pub fn parse_region(input: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid region `{input}`, expected WIDTHxHEIGHT such as 16x16");
+    let (width, height) = input.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width = width.trim().parse::<u32>().map_err(|_| invalid())?;
+    let height = height.trim().parse::<u32>().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+
+    Ok((width, height))
+}
+
+// Generates a soup for a board of the given size, returning it along with the
+// position of its top left corner on the board.
+pub fn generate(params: &SoupParams, board_size: (u32, u32), rng: &mut StdRng) -> (Pattern, (u32, u32)) {
+    let (mut width, mut height) = params.region.unwrap_or(board_size);
+    width = width.min(board_size.0);
+    height = height.min(board_size.1);
+    if params.symmetry.needs_square() {
+        width = width.min(height);
+        height = width;
+    }
+
+    let mut soup = Pattern::new(width, height);
+    let mut visited = vec![false; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            if visited[(y * width + x) as usize] {
+                continue;
+            }
+            let state = if rng.gen::<f64>() < params.density {
+                CellState::Alive
+            } else {
+                CellState::Dead
+            };
+            for (orbit_x, orbit_y) in params.symmetry.orbit((x, y), width, height) {
+                visited[(orbit_y * width + orbit_x) as usize] = true;
+                soup.set(orbit_x, orbit_y, state);
+            }
+        }
+    }
+
+    let offset = ((board_size.0 - width) / 2, (board_size.1 - height) / 2);
+
+    (soup, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::pattern::Transform;
+
+    fn soup(symmetry: Symmetry) -> Pattern {
+        let params = SoupParams {
+            seed: Some(7),
+            region: Some((16, 16)),
+            symmetry,
+            ..Default::default()
+        };
+        generate(&params, (64, 64), &mut StdRng::seed_from_u64(7)).0
+    }
+
+    #[test]
+    fn soups_have_the_requested_symmetry() {
+        let c2 = soup(Symmetry::C2);
+        let half_turn = c2
+            .transformed(Transform::RotateClockwise)
+            .transformed(Transform::RotateClockwise);
+        assert_eq!(half_turn, c2);
+
+        let c4 = soup(Symmetry::C4);
+        assert_eq!(c4.transformed(Transform::RotateClockwise), c4);
+
+        let d4 = soup(Symmetry::D4);
+        assert_eq!(d4.transformed(Transform::FlipHorizontal), d4);
+        assert_eq!(d4.transformed(Transform::FlipVertical), d4);
+    }
+
+    #[test]
+    fn soups_are_centered_on_the_board() {
+        let params = SoupParams {
+            region: Some((16, 16)),
+            ..Default::default()
+        };
+        let (pattern, offset) = generate(&params, (64, 64), &mut StdRng::seed_from_u64(1));
+        assert_eq!((pattern.width, pattern.height), (16, 16));
+        assert_eq!(offset, (24, 24));
+        assert_eq!(parse_region("16x8"), Ok((16, 8)));
+        assert!(parse_region("16").is_err());
+    }
+}
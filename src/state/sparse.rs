@@ -0,0 +1,169 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use super::cell::CellState;
+use super::hensel;
+use super::pattern::Pattern;
+use super::rule::Rule;
+
+// Above this population a pattern is considered to be growing without bound
+// and period detection gives up.
+const MAX_POPULATION: usize = 10_000;
+
+// How a pattern repeats: every `period` generations from generation `onset`
+// on, shifted by `displacement` cells. A still life has period 1 and no
+// displacement; a spaceship has a non-zero displacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Periodicity {
+    pub period: u32,
+    pub onset: u32,
+    pub displacement: (i64, i64),
+}
+
+// Life on an unbounded plane, storing only the live cells. This is used to
+// analyse patterns on their own, away from the edges of any board.
+#[derive(Debug, Clone, Default)]
+pub struct SparseLife {
+    cells: HashSet<(i64, i64)>,
+    rule: Rule,
+}
+
+impl SparseLife {
+    pub fn from_pattern(pattern: &Pattern) -> Self {
+        SparseLife::from_cells(
+            pattern
+                .cells()
+                .filter(|(_, state)| *state == CellState::Alive)
+                .map(|((x, y), _)| (x as i64, y as i64)),
+        )
+    }
+
+    pub fn from_cells(cells: impl IntoIterator<Item = (i64, i64)>) -> Self {
+        SparseLife {
+            cells: cells.into_iter().collect(),
+            rule: Rule::default(),
+        }
+    }
+
+    // Runs under `rule` instead of Conway's Life. Rules where cells are born
+    // with no live neighbors (B0) are not supported, Generations rules run
+    // without their dying states and every rule counts the eight nearest
+    // cells.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+
+        self
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &(i64, i64)> {
+        self.cells.iter()
+    }
+
+    // The top left and bottom right live cells, or `None` when empty.
+    pub fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))> {
+        let min_x = self.cells.iter().map(|(x, _)| *x).min()?;
+        let min_y = self.cells.iter().map(|(_, y)| *y).min()?;
+        let max_x = self.cells.iter().map(|(x, _)| *x).max()?;
+        let max_y = self.cells.iter().map(|(_, y)| *y).max()?;
+
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    pub fn step(&mut self) {
+        let mut configurations: HashMap<(i64, i64), u8> = HashMap::new();
+        for (x, y) in self.cells.iter() {
+            // Each live cell is the neighbor of the cells around it in the
+            // opposite direction.
+            for (bit, (delta_x, delta_y)) in hensel::RING.iter().enumerate() {
+                *configurations
+                    .entry((x - *delta_x as i64, y - *delta_y as i64))
+                    .or_insert(0) |= 1 << bit;
+            }
+        }
+
+        let rule = self.rule;
+        let lonely = self
+            .cells
+            .iter()
+            .filter(|pos| rule.survival.contains_configuration(0) && !configurations.contains_key(pos))
+            .copied()
+            .collect::<Vec<_>>();
+        self.cells = configurations
+            .into_iter()
+            .filter(|(pos, configuration)| rule.next_state_alive(self.cells.contains(pos), *configuration))
+            .map(|(pos, _)| pos)
+            .chain(lonely)
+            .collect();
+    }
+
+    // A hash of the live cells relative to the bounding box, so translated
+    // copies of the same phase hash the same, along with the top left corner.
+    fn normalized_hash(&self) -> (u64, (i64, i64)) {
+        let ((min_x, min_y), _) = self.bounding_box().unwrap_or(((0, 0), (0, 0)));
+        let mut normalized: Vec<(i64, i64)> = self
+            .cells
+            .iter()
+            .map(|(x, y)| (x - min_x, y - min_y))
+            .collect();
+        normalized.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        normalized.hash(&mut hasher);
+
+        (hasher.finish(), (min_x, min_y))
+    }
+}
+
+// Runs a pattern for up to `max_generations` looking for a phase that repeats,
+// possibly translated. Patterns that die out, keep growing or do not settle in
+// time have no periodicity.
+pub fn detect_period(pattern: &Pattern, max_generations: u32) -> Option<Periodicity> {
+    detect_period_of(SparseLife::from_pattern(pattern), max_generations)
+}
+
+// Same as `detect_period`, for cells already on the plane under any rule.
+pub fn detect_period_of(mut life: SparseLife, max_generations: u32) -> Option<Periodicity> {
+    let mut seen: HashMap<u64, (u32, (i64, i64))> = HashMap::new();
+
+    for generation in 0..=max_generations {
+        if life.population() == 0 || life.population() > MAX_POPULATION {
+            return None;
+        }
+        let (hash, (x, y)) = life.normalized_hash();
+        if let Some((onset, (first_x, first_y))) = seen.get(&hash) {
+            return Some(Periodicity {
+                period: generation - onset,
+                onset: *onset,
+                displacement: (x - first_x, y - first_y),
+            });
+        }
+        seen.insert(hash, (generation, (x, y)));
+        life.step();
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::rle::Rle;
+
+    #[test]
+    fn detects_oscillators_and_spaceships() {
+        let blinker = Rle::parse("x = 3, y = 1\n3o!").unwrap();
+        let periodicity = detect_period(&blinker.pattern, 10).unwrap();
+        assert_eq!((periodicity.period, periodicity.displacement), (2, (0, 0)));
+
+        let glider = Rle::parse("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        let periodicity = detect_period(&glider.pattern, 10).unwrap();
+        assert_eq!((periodicity.period, periodicity.displacement), (4, (1, 1)));
+
+        let diehard = Rle::parse("x = 8, y = 3\n6bo$2o$bo3b3o!").unwrap();
+        assert_eq!(detect_period(&diehard.pattern, 200), None);
+    }
+}
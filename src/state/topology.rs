@@ -0,0 +1,64 @@
+use std::fmt;
+use std::str::FromStr;
+
+// How the edges of the board behave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Topology {
+    // Cells past the edge are always dead.
+    #[default]
+    Bounded,
+    // Opposite edges are joined, so patterns leaving one side come back on
+    // the other.
+    Torus,
+}
+
+impl Topology {
+    // Maps a position that may lie past the edge onto the board, or `None`
+    // when it falls off a bounded board.
+    pub fn wrap(&self, (x, y): (isize, isize), (size_x, size_y): (isize, isize)) -> Option<(usize, usize)> {
+        match self {
+            Topology::Bounded => {
+                if x < 0 || x >= size_x || y < 0 || y >= size_y {
+                    return None;
+                }
+                Some((x as usize, y as usize))
+            }
+            Topology::Torus => Some((x.rem_euclid(size_x) as usize, y.rem_euclid(size_y) as usize)),
+        }
+    }
+
+    // The cells at `offsets` from the cell at `(x, y)` that lie on the board,
+    // each as its offset and its position on the board.
+    pub fn neighbors<'a>(
+        &'a self,
+        (x, y): (usize, usize),
+        size: (isize, isize),
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = ((isize, isize), (usize, usize))> + 'a {
+        offsets.iter().filter_map(move |&(delta_x, delta_y)| {
+            let neighbor = self.wrap((x as isize + delta_x, y as isize + delta_y), size)?;
+            Some(((delta_x, delta_y), neighbor))
+        })
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topology::Bounded => write!(f, "bounded"),
+            Topology::Torus => write!(f, "torus"),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "bounded" | "plane" => Ok(Topology::Bounded),
+            "torus" | "wrap" => Ok(Topology::Torus),
+            _ => Err(format!("unknown topology `{s}`, expected bounded or torus")),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::history::Delta;
+use super::selection::Selection;
+
+// A condition checked after every generation. The run pauses as soon as one
+// is met.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    // The population rises above, or falls below, a threshold.
+    PopulationAbove(usize),
+    PopulationBelow(usize),
+    // A cell in the region changes state.
+    RegionChanged(Selection),
+    // The board starts repeating itself.
+    Periodic,
+    // The last live cell dies.
+    Extinct,
+    // The given generation is reached.
+    Generation(u64),
+}
+
+// What a generation did, for checking watches against.
+#[derive(Debug, Clone, Copy)]
+pub struct TickOutcome<'a> {
+    pub previous_population: usize,
+    pub population: usize,
+    pub generation: u64,
+    pub delta: &'a Delta,
+    pub became_periodic: bool,
+}
+
+impl Watch {
+    pub fn is_met(&self, outcome: &TickOutcome) -> bool {
+        match self {
+            Watch::PopulationAbove(threshold) => {
+                outcome.previous_population <= *threshold && outcome.population > *threshold
+            }
+            Watch::PopulationBelow(threshold) => {
+                outcome.previous_population >= *threshold && outcome.population < *threshold
+            }
+            Watch::RegionChanged(region) => outcome
+                .delta
+                .changes()
+                .iter()
+                .any(|change| region.contains(change.pos)),
+            Watch::Periodic => outcome.became_periodic,
+            Watch::Extinct => outcome.previous_population > 0 && outcome.population == 0,
+            Watch::Generation(generation) => outcome.generation == *generation,
+        }
+    }
+}
+
+// Written the way it is parsed, e.g. `pop>100` or `change 0,0 9,9`.
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Watch::PopulationAbove(threshold) => write!(f, "pop>{threshold}"),
+            Watch::PopulationBelow(threshold) => write!(f, "pop<{threshold}"),
+            Watch::RegionChanged(region) => {
+                let (left, top) = region.top_left();
+                let (right, bottom) = region.bottom_right();
+                write!(f, "change {left},{top} {right},{bottom}")
+            }
+            Watch::Periodic => write!(f, "periodic"),
+            Watch::Extinct => write!(f, "extinct"),
+            Watch::Generation(generation) => write!(f, "gen={generation}"),
+        }
+    }
+}
+
+fn parse_corner(corner: &str) -> Option<(u32, u32)> {
+    let (x, y) = corner.split_once(',')?;
+
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+impl FromStr for Watch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let watch = s.trim().to_ascii_lowercase();
+        let number = |value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("expected a whole number in `{s}`"))
+        };
+
+        if let Some(threshold) = watch.strip_prefix("pop>") {
+            return Ok(Watch::PopulationAbove(number(threshold)? as usize));
+        }
+        if let Some(threshold) = watch.strip_prefix("pop<") {
+            return Ok(Watch::PopulationBelow(number(threshold)? as usize));
+        }
+        if let Some(generation) = watch.strip_prefix("gen=") {
+            return Ok(Watch::Generation(number(generation)?));
+        }
+        if let Some(corners) = watch.strip_prefix("change") {
+            let corners: Vec<Option<(u32, u32)>> = corners.split_whitespace().map(parse_corner).collect();
+            return match corners.as_slice() {
+                [Some(anchor), Some(end)] => Ok(Watch::RegionChanged(Selection::new(*anchor, *end))),
+                _ => Err(format!("expected two corners like `change 0,0 9,9`, got `{s}`")),
+            };
+        }
+        match watch.as_str() {
+            "periodic" => Ok(Watch::Periodic),
+            "extinct" => Ok(Watch::Extinct),
+            _ => Err(format!(
+                "unknown watch `{s}`, expected pop>N, pop<N, change X,Y X,Y, periodic, extinct or gen=N"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_it_writes() {
+        for watch in ["pop>100", "pop<3", "change 2,3 10,12", "periodic", "extinct", "gen=500"] {
+            assert_eq!(watch.parse::<Watch>().unwrap().to_string(), watch);
+        }
+        assert_eq!(
+            "change 10,12 2,3".parse::<Watch>().unwrap().to_string(),
+            "change 2,3 10,12"
+        );
+        assert!("pop>lots".parse::<Watch>().is_err());
+        assert!("change 1,1".parse::<Watch>().is_err());
+        assert!("sometimes".parse::<Watch>().is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::prelude::*;
+
+use super::cell::CellState;
+use super::game::Board;
+use super::topology::Topology;
+
+// Largest number of states a totalistic rule may have, so every state of a
+// row can be written as one digit.
+const MAX_STATES: u8 = 9;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WolframRuleError(String);
+
+impl fmt::Display for WolframRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid one-dimensional rule `{}`, expected e.g. W30 (0 to 255) or T1599,K3,R1",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for WolframRuleError {}
+
+// How the first row of a one-dimensional run is filled.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum InitialRow {
+    // One live cell in the middle.
+    #[default]
+    Single,
+    // Each cell alive with the soup density, in a random live state.
+    Random,
+    // The states given, centered on the row.
+    Cells(Vec<u8>),
+}
+
+impl fmt::Display for InitialRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitialRow::Single => write!(f, "single"),
+            InitialRow::Random => write!(f, "random"),
+            InitialRow::Cells(cells) => cells.iter().try_for_each(|state| write!(f, "{state}")),
+        }
+    }
+}
+
+impl FromStr for InitialRow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "single" => Ok(InitialRow::Single),
+            "random" => Ok(InitialRow::Random),
+            cells if !cells.is_empty() && cells.chars().all(|c| c.is_ascii_digit()) => {
+                Ok(InitialRow::Cells(cells.bytes().map(|digit| digit - b'0').collect()))
+            }
+            _ => Err(format!(
+                "unknown initial row `{s}`, expected single, random or the states of its cells, e.g. 1101"
+            )),
+        }
+    }
+}
+
+// A one-dimensional automaton in Wolfram's numbering, run on the bottom row
+// of the board while earlier generations scroll up above it. Elementary
+// rules (`W30`) look up the two states and three cells around each cell as
+// a binary number; totalistic rules (`T1599,K3,R1`) add up the `K` states
+// of the cells within `R` and take that digit of the code in base `K`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WolframRule {
+    pub code: u64,
+    pub states: u8,
+    pub radius: u8,
+    pub totalistic: bool,
+    pub initial: InitialRow,
+}
+
+impl WolframRule {
+    pub fn elementary(code: u8) -> Self {
+        WolframRule {
+            code: code as u64,
+            states: 2,
+            radius: 1,
+            totalistic: false,
+            initial: InitialRow::default(),
+        }
+    }
+
+    pub fn with_initial_row(mut self, initial: InitialRow) -> Self {
+        self.initial = initial;
+
+        self
+    }
+
+    // The next state of a cell from the states of the cells around it, in
+    // order from the left. States the rule doesn't have, e.g. from a pattern
+    // for another rule, count as state 0.
+    pub fn next_state(&self, window: &[u8]) -> u8 {
+        let states = self.states as u64;
+        let known = window.iter().map(|state| if *state < self.states { *state as u32 } else { 0 });
+        let digit = if self.totalistic {
+            known.sum()
+        } else {
+            known.fold(0, |digit, state| digit * self.states as u32 + state)
+        };
+
+        (self.code / states.pow(digit) % states) as u8
+    }
+
+    // Every row moves up one, the top row falling off the board, and the
+    // bottom row gets the next generation of the row that was there.
+    pub fn next_states(&self, board: &Board, topology: Topology) -> Vec<Vec<CellState>> {
+        let size = (board.len() as isize, board.first().map_or(0, |column| column.len()) as isize);
+        let state = |(x, y): (usize, usize)| board[x][y].as_ref().map_or(CellState::Dead, |cell| cell.state);
+        let bottom = size.1 - 1;
+        let radius = self.radius as isize;
+
+        (0..size.0)
+            .map(|x| {
+                let window: Vec<u8> = (x - radius..=x + radius)
+                    .map(|neighbor| topology.wrap((neighbor, bottom), size).map_or(0, |pos| state(pos).index()))
+                    .collect();
+                let next = CellState::from_index(self.next_state(&window));
+                (0..size.1)
+                    .map(|y| if y == bottom { next } else { state((x as usize, y as usize + 1)) })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // The states of the first row of a board `width` cells wide.
+    pub fn initial_row(&self, width: usize, density: f64, rng: &mut impl Rng) -> Vec<CellState> {
+        let states = match &self.initial {
+            InitialRow::Single => (0..width).map(|x| u8::from(x == width / 2)).collect(),
+            InitialRow::Random => (0..width)
+                .map(|_| if rng.gen_bool(density) { rng.gen_range(1..self.states) } else { 0 })
+                .collect(),
+            InitialRow::Cells(cells) => {
+                let offset = (width as isize - cells.len() as isize) / 2;
+                (0..width as isize)
+                    .map(|x| cells.get((x - offset) as usize).copied().unwrap_or(0) % self.states)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        states.into_iter().map(CellState::from_index).collect()
+    }
+}
+
+impl fmt::Display for WolframRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.totalistic {
+            write!(f, "T{},K{},R{}", self.code, self.states, self.radius)
+        } else {
+            write!(f, "W{}", self.code)
+        }
+    }
+}
+
+impl FromStr for WolframRule {
+    type Err = WolframRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || WolframRuleError(s.to_string());
+        let rule = s.trim().to_ascii_uppercase();
+        if let Some(code) = rule.strip_prefix('W') {
+            return code.parse().map(WolframRule::elementary).map_err(|_| invalid());
+        }
+
+        let mut parts = rule.strip_prefix('T').ok_or_else(invalid)?.split(',');
+        let code = parts.next().and_then(|code| code.parse::<u64>().ok()).ok_or_else(invalid)?;
+        let (mut states, mut radius) = (2, 1);
+        for part in parts {
+            let (setting, value) = part.split_at(part.chars().next().map_or(0, char::len_utf8));
+            let value = value.parse::<u8>().map_err(|_| invalid())?;
+            match setting {
+                "K" if (2..=MAX_STATES).contains(&value) => states = value,
+                "R" if value >= 1 => radius = value,
+                _ => return Err(invalid()),
+            }
+        }
+        // The code has a digit for every sum from zero to all the cells in
+        // the last state.
+        let sums = (states as u32 - 1) * (2 * radius as u32 + 1) + 1;
+        match (states as u64).checked_pow(sums) {
+            Some(codes) if code < codes => Ok(WolframRule {
+                code,
+                states,
+                radius,
+                totalistic: true,
+                initial: InitialRow::default(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::game::Game;
+    use crate::state::soup::SoupParams;
+
+    #[test]
+    fn rows_scroll_up_under_the_next_generation() {
+        let rule: WolframRule = "w90".parse().unwrap();
+        assert_eq!(rule.to_string(), "W90");
+        assert_eq!("T1599,K3,R1".parse::<WolframRule>().unwrap().to_string(), "T1599,K3,R1");
+        assert!("W256".parse::<WolframRule>().is_err());
+        assert!("T2187,K3,R1".parse::<WolframRule>().is_err());
+        assert_eq!("1021".parse::<InitialRow>(), Ok(InitialRow::Cells(vec![1, 0, 2, 1])));
+
+        // Rule 90 draws a Sierpinski triangle, one row per generation.
+        let mut game = Game::builder()
+            .with_size(7, 4)
+            .with_wolfram_rule(rule)
+            .with_soup(SoupParams::default())
+            .build()
+            .unwrap();
+        game.advance(3);
+        while game.is_jumping() {
+            game.jump_tick();
+        }
+        let rows: Vec<String> = (0..4)
+            .map(|y| (0..7).map(|x| if game.cell_state((x, y)) == Some(CellState::Alive) { '#' } else { '.' }).collect())
+            .collect();
+        assert_eq!(rows, vec!["...#...", "..#.#..", ".#...#.", "#.#.#.#"]);
+
+        // 1599 is 2012020 in base 3: a total of 1 gives state 2, 4 state 1.
+        let totalistic: WolframRule = "T1599,K3,R1".parse().unwrap();
+        assert_eq!(totalistic.next_state(&[0, 1, 0]), 2);
+        assert_eq!(totalistic.next_state(&[2, 1, 1]), 1);
+        assert_eq!(totalistic.next_state(&[0, 0, 0]), 0);
+        assert_eq!(WolframRule::elementary(90).next_state(&[200, 1, 255]), 0);
+    }
+}
@@ -0,0 +1,441 @@
+use cursive::{
+    event::{Event, EventResult, Key, MouseButton, MouseEvent},
+    theme::{BaseColor, Color, ColorStyle, Effect},
+    view::Nameable,
+    views::{Canvas, Dialog, EditView},
+    Cursive, Printer, Vec2,
+};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::watch::Receiver;
+
+use crate::state::{
+    cell::CellState, game::GameData, neighborhood::Shape, pattern::Transform, selection::Selection,
+    simulation_controls::ControlMessages, watch::Watch,
+};
+
+const DEFAULT_FILL_DENSITY: f64 = 0.5;
+// Colors of dying cells under a Generations rule, from those that just died
+// on. Cells further along keep the last color.
+const DYING_COLORS: [Color; 4] = [
+    Color::Light(BaseColor::Yellow),
+    Color::Light(BaseColor::Red),
+    Color::Dark(BaseColor::Red),
+    Color::Dark(BaseColor::Magenta),
+];
+// Background colors of the activity heatmap, from the least to the most
+// active cells.
+const HEATMAP_COLORS: [Color; 4] = [
+    Color::Dark(BaseColor::Blue),
+    Color::Dark(BaseColor::Magenta),
+    Color::Dark(BaseColor::Red),
+    Color::Light(BaseColor::Yellow),
+];
+
+// State backing the board canvas: the model to draw, the channel edits are
+// sent on, and the editing cursor.
+pub struct BoardState {
+    model_rx: Receiver<GameData>,
+    controls_tx: Sender<ControlMessages>,
+    pub cursor: (u32, u32),
+    // The state being painted by the current mouse stroke, if any.
+    stroke: Option<CellState>,
+    selection: Option<Selection>,
+    // Whether moving the cursor extends the selection.
+    selecting: bool,
+    // Whether the clipboard follows the cursor as a ghost waiting to be
+    // stamped.
+    pasting: bool,
+    // Whether cells are shaded by how often they changed recently.
+    heatmap: bool,
+    // The state painted onto empty cells, picked from the palette of a rule
+    // file.
+    brush: CellState,
+}
+
+impl BoardState {
+    pub fn new(model_rx: Receiver<GameData>, controls_tx: Sender<ControlMessages>) -> Self {
+        BoardState {
+            model_rx,
+            controls_tx,
+            cursor: (0, 0),
+            stroke: None,
+            selection: None,
+            selecting: false,
+            pasting: false,
+            heatmap: false,
+            brush: CellState::Alive,
+        }
+    }
+
+    pub fn draw(&self, printer: &Printer) {
+        let board = self.model_rx.borrow();
+        let previous_board = board.previous_generation.iter().flatten();
+        let next_board = board.cells.iter().flatten();
+        let zipped_boards = next_board.zip(previous_board);
+        let hottest = board.activity.iter().flatten().copied().max().unwrap_or(0);
+        let hexagonal = is_hexagonal(&board);
+        tracing::debug!("Drawing board.");
+        for (new_cell, old_cell) in zipped_boards {
+            if let (Some(inner), Some(old_inner)) = (new_cell.as_ref(), old_cell.as_ref()) {
+                // Rule files color every state, so each is drawn solid.
+                let colored = board.colors.get(inner.state.index() as usize).filter(|_| inner.state != CellState::Dead);
+                let char_to_print = match (&inner.state, &old_inner.state) {
+                    (_, _) if colored.is_some() => '█',
+                    (CellState::Alive, CellState::Alive) => '█',
+                    (CellState::Alive, _) => '▓',
+                    (CellState::Dead, CellState::Alive) => '▒',
+                    (CellState::Dead, _) => ' ',
+                    (CellState::Dying(1), _) => '▒',
+                    (CellState::Dying(_), _) => '░',
+                };
+                let at = screen_position(hexagonal, inner.pos);
+                let glyph = char_to_print.to_string().repeat(cell_width(hexagonal));
+
+                let selected = self.selection.is_some_and(|selection| selection.contains(inner.pos));
+                let triggered = board.triggered.iter().any(|watch| match watch {
+                    Watch::RegionChanged(region) => region.contains(inner.pos),
+                    _ => false,
+                });
+                let activity = board
+                    .activity
+                    .get(inner.x() as usize)
+                    .and_then(|column| column.get(inner.y() as usize))
+                    .copied()
+                    .unwrap_or(0);
+                if printer.focused && inner.pos == self.cursor {
+                    printer.with_effect(Effect::Reverse, |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else if selected {
+                    printer.with_color(ColorStyle::highlight_inactive(), |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else if triggered {
+                    let style = ColorStyle::new(Color::Light(BaseColor::White), Color::Dark(BaseColor::Red));
+                    printer.with_color(style, |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else if self.heatmap && activity > 0 {
+                    let level = (activity as usize * HEATMAP_COLORS.len()).div_ceil(hottest as usize) - 1;
+                    let style = ColorStyle::new(Color::Light(BaseColor::White), HEATMAP_COLORS[level]);
+                    printer.with_color(style, |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else if let Some((r, g, b)) = colored {
+                    printer.with_color(ColorStyle::front(Color::Rgb(*r, *g, *b)), |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else if let CellState::Dying(age) = inner.state {
+                    let color = DYING_COLORS[(age as usize).saturating_sub(1).min(DYING_COLORS.len() - 1)];
+                    printer.with_color(ColorStyle::front(color), |printer| {
+                        printer.print(at, &glyph)
+                    });
+                } else {
+                    printer.print(at, &glyph)
+                }
+            }
+        }
+
+        if self.pasting {
+            if let Some(pattern) = board.clipboard.as_ref() {
+                let (x, y) = self.cursor;
+                printer.with_color(ColorStyle::secondary(), |printer| {
+                    for ((offset_x, offset_y), state) in pattern.cells() {
+                        if state == CellState::Alive {
+                            let at = screen_position(hexagonal, (x + offset_x, y + offset_y));
+                            printer.print(at, &"░".repeat(cell_width(hexagonal)));
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    pub fn on_event(&mut self, event: Event) -> EventResult {
+        let (size_x, size_y) = self.board_size();
+        match event {
+            Event::Key(Key::Left) if self.cursor.0 > 0 => self.move_cursor((self.cursor.0 - 1, self.cursor.1)),
+            Event::Key(Key::Right) if self.cursor.0 + 1 < size_x => {
+                self.move_cursor((self.cursor.0 + 1, self.cursor.1))
+            }
+            Event::Key(Key::Up) if self.cursor.1 > 0 => self.move_cursor((self.cursor.0, self.cursor.1 - 1)),
+            Event::Key(Key::Down) if self.cursor.1 + 1 < size_y => {
+                self.move_cursor((self.cursor.0, self.cursor.1 + 1))
+            }
+            Event::Char(' ') | Event::Key(Key::Enter) if self.pasting => {
+                self.send(ControlMessages::Paste(self.cursor));
+            }
+            Event::Char(' ') | Event::Key(Key::Enter) => {
+                let state = self.toggled_state(self.cursor);
+                self.send(ControlMessages::Paint {
+                    pos: self.cursor,
+                    state,
+                });
+            }
+            Event::Key(Key::Esc) if self.pasting => self.pasting = false,
+            Event::Key(Key::Esc) if self.selection.is_some() => {
+                self.selection = None;
+                self.selecting = false;
+            }
+            Event::Char('s') => {
+                if self.selecting {
+                    self.selecting = false;
+                } else {
+                    self.selection = Some(Selection::new(self.cursor, self.cursor));
+                    self.selecting = true;
+                }
+            }
+            Event::Char(digit @ '1'..='9') => {
+                self.brush = CellState::from_index(digit as u8 - b'0');
+            }
+            Event::Char('b') => {
+                let states = self.model_rx.borrow().colors.len();
+                let brush = self.brush.index();
+                return EventResult::with_cb(move |cursive| brush_dialog(cursive, states, brush));
+            }
+            Event::Char('a') => self.heatmap = !self.heatmap,
+            Event::Char('A') => self.send(ControlMessages::ResetActivity),
+            Event::Char('p') if self.model_rx.borrow().clipboard.is_some() => self.pasting = !self.pasting,
+            Event::Char('r') => self.transform(Transform::RotateClockwise),
+            Event::Char('h') => self.transform(Transform::FlipHorizontal),
+            Event::Char('v') => self.transform(Transform::FlipVertical),
+            Event::Char('c' | 'x' | 'd' | 'n') | Event::Key(Key::Del)
+                if self.selection.is_some() =>
+            {
+                return self.on_selection_key(event);
+            }
+            Event::Mouse {
+                offset,
+                position,
+                event,
+            } => {
+                let Some(pos) = self.board_position(position, offset) else {
+                    return EventResult::Ignored;
+                };
+                match event {
+                    MouseEvent::Press(MouseButton::Left) if self.pasting => {
+                        self.cursor = pos;
+                        self.send(ControlMessages::Paste(pos));
+                    }
+                    MouseEvent::Press(MouseButton::Left) => {
+                        // A stroke paints whatever the first cell toggles to.
+                        let state = self.toggled_state(pos);
+                        self.cursor = pos;
+                        self.stroke = Some(state);
+                        self.send(ControlMessages::BeginStroke);
+                        self.send(ControlMessages::Paint { pos, state });
+                    }
+                    MouseEvent::Hold(MouseButton::Left) => {
+                        if let Some(state) = self.stroke {
+                            self.cursor = pos;
+                            self.send(ControlMessages::Paint { pos, state });
+                        }
+                    }
+                    MouseEvent::Release(MouseButton::Left) => {
+                        if self.stroke.take().is_some() {
+                            self.send(ControlMessages::EndStroke);
+                        }
+                    }
+                    MouseEvent::Press(MouseButton::Right) => {
+                        self.cursor = pos;
+                        self.selection = Some(Selection::new(pos, pos));
+                        self.selecting = true;
+                    }
+                    MouseEvent::Hold(MouseButton::Right) => self.move_cursor(pos),
+                    MouseEvent::Release(MouseButton::Right) => self.selecting = false,
+                    _ => return EventResult::Ignored,
+                }
+            }
+            _ => return EventResult::Ignored,
+        }
+
+        EventResult::Consumed(None)
+    }
+
+    fn on_selection_key(&mut self, event: Event) -> EventResult {
+        let Some(selection) = self.selection else {
+            return EventResult::Ignored;
+        };
+        match event {
+            Event::Char('c') => self.send(ControlMessages::Copy(selection)),
+            Event::Char('x') => self.send(ControlMessages::Cut(selection)),
+            Event::Char('d') | Event::Key(Key::Del) => self.send(ControlMessages::Clear(selection)),
+            Event::Char('n') => {
+                let controls_tx = self.controls_tx.clone();
+                return EventResult::with_cb(move |cursive| {
+                    fill_random_dialog(cursive, controls_tx.clone(), selection)
+                });
+            }
+            _ => return EventResult::Ignored,
+        }
+
+        EventResult::Consumed(None)
+    }
+
+    // Rotates or flips the clipboard while pasting, the selection otherwise.
+    fn transform(&mut self, transform: Transform) {
+        if self.pasting {
+            self.send(ControlMessages::TransformClipboard(transform));
+        } else if let Some(selection) = self.selection {
+            self.send(ControlMessages::TransformSelection(selection, transform));
+            if transform == Transform::RotateClockwise {
+                self.selection = Some(selection.rotated());
+            }
+        }
+    }
+
+    pub fn selection(&self) -> Option<Selection> {
+        self.selection
+    }
+
+    // The brush, or Alive once the rule of `game_data` no longer has its
+    // state.
+    pub fn brush(&self, game_data: &GameData) -> CellState {
+        if (self.brush.index() as usize) < game_data.colors.len() {
+            self.brush
+        } else {
+            CellState::Alive
+        }
+    }
+
+    fn move_cursor(&mut self, pos: (u32, u32)) {
+        self.cursor = pos;
+        if self.selecting {
+            if let Some(selection) = self.selection.as_mut() {
+                selection.end = pos;
+            }
+        }
+    }
+
+    // Room for the whole board, or as much of it as fits.
+    pub fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+        let (size_x, size_y) = self.board_size();
+        let width = if is_hexagonal(&self.model_rx.borrow()) {
+            // Odd rows reach one character further right.
+            size_x as usize * cell_width(true) + 1
+        } else {
+            size_x as usize
+        };
+
+        Vec2::new(width.min(constraint.x), (size_y as usize).min(constraint.y))
+    }
+
+    fn board_size(&self) -> (u32, u32) {
+        let board = self.model_rx.borrow();
+        let size_x = board.cells.len() as u32;
+        let size_y = board.cells.first().map_or(0, |column| column.len()) as u32;
+
+        (size_x, size_y)
+    }
+
+    fn board_position(&self, position: Vec2, offset: Vec2) -> Option<(u32, u32)> {
+        let relative = position.checked_sub(offset)?;
+        let (size_x, size_y) = self.board_size();
+        let (x, y) = if is_hexagonal(&self.model_rx.borrow()) {
+            let x = relative.x.checked_sub(relative.y % 2)? / 2;
+            (x as u32, relative.y as u32)
+        } else {
+            (relative.x as u32, relative.y as u32)
+        };
+        if x >= size_x || y >= size_y {
+            return None;
+        }
+
+        Some((x, y))
+    }
+
+    fn toggled_state(&self, (x, y): (u32, u32)) -> CellState {
+        let board = self.model_rx.borrow();
+        let brush = self.brush(&board);
+        match board.cells.get(x as usize).and_then(|column| column.get(y as usize)) {
+            Some(Some(cell)) if cell.state == brush => CellState::Dead,
+            _ => brush,
+        }
+    }
+
+    fn send(&self, message: ControlMessages) {
+        if let Err(error) = self.controls_tx.try_send(message) {
+            tracing::error!("Unable to send edit message on controls sender channel. {:?}", error);
+        }
+    }
+}
+
+// Whether the board runs a hexagonal rule, and is drawn as a hex grid.
+fn is_hexagonal(game_data: &GameData) -> bool {
+    game_data.shape == Shape::Hexagonal
+}
+
+// Where the cell at `pos` is drawn. A hex grid draws each cell two
+// characters wide with odd rows shifted one character right, so each cell
+// sits between its neighbors in the rows above and below.
+fn screen_position(hexagonal: bool, (x, y): (u32, u32)) -> (u32, u32) {
+    if hexagonal {
+        (2 * x + y % 2, y)
+    } else {
+        (x, y)
+    }
+}
+
+fn cell_width(hexagonal: bool) -> usize {
+    if hexagonal {
+        2
+    } else {
+        1
+    }
+}
+
+// Asks for the density to randomly fill the selection with.
+// Asks for the state to paint with, for rules with more states than there
+// are digit keys.
+fn brush_dialog(cursive: &mut Cursive, states: usize, brush: u8) {
+    cursive.add_layer(
+        Dialog::around(EditView::new().content(brush.to_string()).with_name("Brush state"))
+            .title(format!("Paint with state (1-{})", states.saturating_sub(1)))
+            .button("Pick", move |cursive| {
+                let state = cursive
+                    .call_on_name("Brush state", |view: &mut EditView| view.get_content())
+                    .and_then(|content| content.trim().parse::<u8>().ok())
+                    .filter(|state| (1..states).contains(&(*state as usize)));
+                match state {
+                    Some(state) => {
+                        cursive.call_on_name("Board", |board: &mut Canvas<BoardState>| {
+                            board.state_mut().brush = CellState::from_index(state)
+                        });
+                        cursive.pop_layer();
+                    }
+                    None => cursive.add_layer(Dialog::info(format!(
+                        "The state must be a whole number from 1 to {}.",
+                        states.saturating_sub(1)
+                    ))),
+                }
+            })
+            .dismiss_button("Cancel"),
+    );
+}
+
+fn fill_random_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, selection: Selection) {
+    cursive.add_layer(
+        Dialog::around(
+            EditView::new()
+                .content(DEFAULT_FILL_DENSITY.to_string())
+                .with_name("Fill density"),
+        )
+        .title("Random fill density (0-1)")
+        .button("Fill", move |cursive| {
+            let density = cursive
+                .call_on_name("Fill density", |view: &mut EditView| view.get_content())
+                .and_then(|content| content.parse::<f64>().ok())
+                .filter(|density| (0.0..=1.0).contains(density));
+            match density {
+                Some(density) => {
+                    if let Err(error) = controls_tx.try_send(ControlMessages::FillRandom(selection, density)) {
+                        tracing::error!("Unable to send fill message on controls sender channel. {:?}", error);
+                    }
+                    cursive.pop_layer();
+                }
+                None => cursive.add_layer(Dialog::info("Density must be a number between 0 and 1.")),
+            }
+        })
+        .dismiss_button("Cancel"),
+    );
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use cursive::{
+    view::{Nameable, Resizable, Scrollable},
+    views::{Dialog, EditView, LinearLayout, TextView},
+    Cursive,
+};
+
+use crate::state::{census::Census, game::GameData, rule::Rule};
+
+const DEFAULT_EXPORT_PATH: &str = "census.csv";
+
+// Splits the board into objects and shows how many of each there are, with
+// buttons to export the table.
+pub fn census_dialog(cursive: &mut Cursive, game_data: &GameData) {
+    let Ok(rule) = game_data.rule.parse::<Rule>() else {
+        cursive.add_layer(Dialog::info(format!(
+            "The census only knows B/S rules, not {}.",
+            game_data.rule
+        )));
+        return;
+    };
+    let census = Census::of_board(&game_data.cells, rule);
+
+    let mut table = format!("{:>6}  {:<11} {:>6} {:>4}  {}\n", "Count", "Kind", "Period", "Pop", "apgcode");
+    for entry in census.entries.iter() {
+        let period = entry.period.map(|period| period.to_string()).unwrap_or_default();
+        table.push_str(&format!(
+            "{:>6}  {:<11} {:>6} {:>4}  {}\n",
+            entry.count, entry.kind, period, entry.population, entry.apgcode
+        ));
+    }
+    if census.entries.is_empty() {
+        table.push_str("The board is empty.\n");
+    }
+
+    let csv_census = census.clone();
+    let json_census = census.clone();
+    cursive.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new(table).scrollable().max_height(20))
+                .child(TextView::new("Export to:"))
+                .child(
+                    EditView::new()
+                        .content(DEFAULT_EXPORT_PATH)
+                        .with_name("Census path")
+                        .min_width(40),
+                ),
+        )
+        .title(format!(
+            "Census: {} objects at generation {}",
+            census.object_count(),
+            game_data.generation
+        ))
+        .button("Export CSV", move |cursive| export(cursive, csv_census.to_csv()))
+        .button("Export JSON", move |cursive| export(cursive, json_census.to_json()))
+        .dismiss_button("Close"),
+    );
+}
+
+fn export(cursive: &mut Cursive, contents: String) {
+    let path = cursive
+        .call_on_name("Census path", |view: &mut EditView| view.get_content())
+        .map(|content| PathBuf::from(content.trim()))
+        .unwrap_or_default();
+    let message = match std::fs::write(&path, contents) {
+        Ok(()) => format!("Census written to {}.", path.display()),
+        Err(error) => format!("Unable to write {}: {error}", path.display()),
+    };
+    cursive.add_layer(Dialog::info(message));
+}
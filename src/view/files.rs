@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use cursive::{
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::simulation_controls::ControlMessages;
+
+const DEFAULT_SAVE_PATH: &str = "rust-of-life.rle";
+
+pub fn save_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>) {
+    path_dialog(cursive, "Save board", "Save", ControlMessages::Save, controls_tx);
+}
+
+pub fn load_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>) {
+    path_dialog(cursive, "Load board", "Load", ControlMessages::Load, controls_tx);
+}
+
+// Asks for a file path and sends the message built from it.
+fn path_dialog(
+    cursive: &mut Cursive,
+    title: &str,
+    action: &str,
+    message: impl 'static + Fn(PathBuf) -> ControlMessages,
+    controls_tx: Sender<ControlMessages>,
+) {
+    cursive.add_layer(
+        Dialog::around(
+            EditView::new()
+                .content(DEFAULT_SAVE_PATH)
+                .with_name("File path")
+                .min_width(40),
+        )
+        .title(title)
+        .button(action, move |cursive| {
+            let path = cursive
+                .call_on_name("File path", |view: &mut EditView| view.get_content())
+                .map(|content| PathBuf::from(content.trim()))
+                .unwrap_or_default();
+            if let Err(error) = controls_tx.try_send(message(path)) {
+                tracing::error!("Unable to send file message on controls sender channel. {:?}", error);
+            }
+            cursive.pop_layer();
+        })
+        .dismiss_button("Cancel"),
+    );
+}
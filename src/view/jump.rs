@@ -0,0 +1,80 @@
+use cursive::{
+    utils::Counter,
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, ProgressBar},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::{game::GameData, simulation_controls::ControlMessages};
+
+// Steps of the progress bar, to show the jump's progress to a tenth of a
+// percent.
+const PROGRESS_STEPS: usize = 1000;
+const DEFAULT_JUMP_LENGTH: u64 = 1000;
+
+// Asks for a later generation and jumps the board to it.
+pub fn jump_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, generation: u64) {
+    cursive.add_layer(
+        Dialog::around(
+            EditView::new()
+                .content((generation + DEFAULT_JUMP_LENGTH).to_string())
+                .with_name("Jump generation")
+                .fixed_width(20),
+        )
+        .title(format!("Jump from generation {generation} to"))
+        .button("Jump", move |cursive| {
+            let target = cursive
+                .call_on_name("Jump generation", |view: &mut EditView| view.get_content())
+                .and_then(|content| content.trim().parse::<u64>().ok());
+            match target {
+                Some(target) if target > generation => {
+                    // The run may go on while the dialog is open, so the
+                    // game works out how far that is.
+                    if let Err(error) = controls_tx.try_send(ControlMessages::JumpTo(target)) {
+                        tracing::error!("Unable to send jump message on controls sender channel. {:?}", error);
+                    }
+                    cursive.pop_layer();
+                }
+                Some(_) => cursive.add_layer(Dialog::info(format!(
+                    "Pick a generation after {generation}; Step Back and the timeline go backwards."
+                ))),
+                None => cursive.add_layer(Dialog::info("The generation must be a whole number.")),
+            }
+        })
+        .dismiss_button("Cancel"),
+    );
+}
+
+// Shows a progress bar with a cancel button while a jump is running, and
+// takes it down once the jump ends.
+pub fn sync_jump_progress(cursive: &mut Cursive, game_data: &GameData, controls_tx: &Sender<ControlMessages>) {
+    let Some(jump) = game_data.jump else {
+        if let Some(position) = cursive.screen_mut().find_layer_from_name("Jump progress dialog") {
+            cursive.screen_mut().remove_layer(position);
+        }
+        return;
+    };
+
+    let value = (jump.progress(game_data.generation) * PROGRESS_STEPS as f64) as usize;
+    let shown = cursive.call_on_name("Jump progress", |bar: &mut ProgressBar| bar.set_value(value));
+    if shown.is_none() {
+        let cancel_tx = controls_tx.clone();
+        cursive.add_layer(
+            Dialog::around(
+                ProgressBar::new()
+                    .range(0, PROGRESS_STEPS)
+                    .with_value(Counter::new(value))
+                    .with_name("Jump progress")
+                    .min_width(40),
+            )
+            .title(format!("Jumping to generation {}", jump.to))
+            .button("Cancel", move |_cursive| {
+                if let Err(error) = cancel_tx.try_send(ControlMessages::CancelJump) {
+                    tracing::error!("Unable to send cancel message on controls sender channel. {:?}", error);
+                }
+            })
+            .with_name("Jump progress dialog"),
+        );
+    }
+}
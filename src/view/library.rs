@@ -0,0 +1,132 @@
+use cursive::{
+    view::{Nameable, Resizable, Scrollable},
+    views::{Canvas, Dialog, LinearLayout, Panel, SelectView, TextView},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::{
+    cell::CellState,
+    library::{patterns_in, Category, LibraryPattern, BUILTIN_PATTERNS},
+    pattern::Pattern,
+    rle::Rle,
+    simulation_controls::ControlMessages,
+};
+
+use super::board::BoardState;
+
+const PREVIEW_WIDTH: usize = 40;
+const PREVIEW_HEIGHT: usize = 24;
+
+// Renders a pattern as text for previews, cropped to the preview area.
+pub fn preview(pattern: &Pattern) -> String {
+    (0..pattern.height.min(PREVIEW_HEIGHT as u32))
+        .map(|y| {
+            (0..pattern.width.min(PREVIEW_WIDTH as u32))
+                .map(|x| match pattern.get(x, y) {
+                    CellState::Alive => '█',
+                    CellState::Dead => '·',
+                    CellState::Dying(_) => '░',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A short summary of a pattern and its metadata for the preview pane.
+pub fn describe(rle: &Rle) -> String {
+    let mut description = format!(
+        "{}x{}, {} cells",
+        rle.pattern.width,
+        rle.pattern.height,
+        rle.pattern.population()
+    );
+    if let Some(author) = rle.author.as_ref() {
+        description.push_str(&format!("\nBy {author}"));
+    }
+    for comment in rle.comments.iter() {
+        description.push_str(&format!("\n{comment}"));
+    }
+
+    description
+}
+
+// Where a chosen pattern gets stamped: the cursor on the board canvas.
+pub fn board_cursor(cursive: &mut Cursive) -> (u32, u32) {
+    cursive
+        .call_on_name("Board", |board: &mut Canvas<BoardState>| board.state_mut().cursor)
+        .unwrap_or((0, 0))
+}
+
+// Opens a dialog to browse the built-in patterns by category, preview them
+// and stamp the chosen one onto the board at the cursor.
+pub fn pattern_browser(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>) {
+    let categories = SelectView::new()
+        .with_all(Category::ALL.iter().map(|category| (category.to_string(), *category)))
+        .on_select(|cursive, category: &Category| {
+            cursive.call_on_name("Library patterns", |patterns: &mut SelectView<&'static LibraryPattern>| {
+                patterns.clear();
+                patterns.add_all(patterns_in(*category).map(|pattern| (pattern.name, pattern)));
+            });
+            if let Some(first) = patterns_in(*category).next() {
+                show_preview(cursive, first);
+            }
+        });
+
+    let patterns = SelectView::<&'static LibraryPattern>::new()
+        .with_all(patterns_in(Category::ALL[0]).map(|pattern| (pattern.name, pattern)))
+        .on_select(|cursive, pattern| show_preview(cursive, pattern))
+        .with_name("Library patterns")
+        .scrollable()
+        .min_width(26);
+
+    let preview_pane = LinearLayout::vertical()
+        .child(TextView::new("").with_name("Library preview"))
+        .child(TextView::new("").with_name("Library description"))
+        .min_width(PREVIEW_WIDTH);
+
+    cursive.add_layer(
+        Dialog::around(
+            LinearLayout::horizontal()
+                .child(Panel::new(categories).title("Category"))
+                .child(Panel::new(patterns).title("Pattern"))
+                .child(Panel::new(preview_pane).title("Preview")),
+        )
+        .title("Pattern library")
+        .button("Stamp", move |cursive| {
+            let chosen = cursive
+                .call_on_name("Library patterns", |patterns: &mut SelectView<&'static LibraryPattern>| {
+                    patterns.selection()
+                })
+                .flatten();
+            let Some(chosen) = chosen else {
+                return;
+            };
+            match chosen.load() {
+                Ok(rle) => {
+                    let pos = board_cursor(cursive);
+                    if let Err(error) = controls_tx.try_send(ControlMessages::Stamp(rle.pattern, pos)) {
+                        tracing::error!("Unable to send stamp message on controls sender channel. {:?}", error);
+                    }
+                    cursive.pop_layer();
+                }
+                Err(error) => tracing::error!("Built-in pattern {} failed to load. {error}", chosen.name),
+            }
+        })
+        .dismiss_button("Close"),
+    );
+
+    if let Some(first) = BUILTIN_PATTERNS.first() {
+        show_preview(cursive, first);
+    }
+}
+
+fn show_preview(cursive: &mut Cursive, pattern: &LibraryPattern) {
+    let (preview_text, description) = match pattern.load() {
+        Ok(rle) => (preview(&rle.pattern), describe(&rle)),
+        Err(error) => (String::new(), format!("Unable to load pattern: {error}")),
+    };
+    cursive.call_on_name("Library preview", |view: &mut TextView| view.set_content(preview_text));
+    cursive.call_on_name("Library description", |view: &mut TextView| view.set_content(description));
+}
@@ -1 +1,9 @@
+pub mod board;
+pub mod census;
+pub mod files;
+pub mod jump;
+pub mod library;
+pub mod search;
+pub mod soup;
 pub mod ui;
+pub mod watches;
@@ -0,0 +1,189 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use cursive::{
+    view::{Nameable, Resizable, Scrollable},
+    views::{Dialog, EditView, LinearLayout, Panel, SelectView, TextView},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::index::{IndexEntry, PatternIndex, Query};
+use crate::state::simulation_controls::ControlMessages;
+
+use super::library::{board_cursor, describe, preview};
+
+// Environment variable naming the pattern directory offered by default.
+const PATTERN_DIR_VARIABLE: &str = "RUST_OF_LIFE_PATTERN_DIR";
+// Only this many matches are listed, to keep the dialog responsive.
+const MAX_RESULTS: usize = 500;
+
+pub type SharedIndex = Arc<Mutex<Option<PatternIndex>>>;
+
+// Opens the search over the indexed pattern directory, asking for the
+// directory first if none has been indexed yet.
+pub fn pattern_search(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, index: SharedIndex) {
+    let indexed = index.lock().map(|index| index.is_some()).unwrap_or(false);
+    if indexed {
+        search_dialog(cursive, controls_tx, index);
+    } else {
+        directory_dialog(cursive, controls_tx, index);
+    }
+}
+
+fn directory_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, index: SharedIndex) {
+    let current = index
+        .lock()
+        .ok()
+        .and_then(|index| index.as_ref().map(|index| index.root.to_string_lossy().to_string()))
+        .or_else(|| std::env::var(PATTERN_DIR_VARIABLE).ok())
+        .unwrap_or_else(|| ".".to_string());
+
+    cursive.add_layer(
+        Dialog::around(EditView::new().content(current).with_name("Pattern directory").min_width(50))
+            .title("Pattern directory")
+            .button("Index", move |cursive| {
+                let directory = cursive
+                    .call_on_name("Pattern directory", |view: &mut EditView| view.get_content())
+                    .map(|content| PathBuf::from(content.as_str()))
+                    .unwrap_or_default();
+                cursive.pop_layer();
+                index_directory(cursive, directory, controls_tx.clone(), index.clone());
+            })
+            .dismiss_button("Cancel"),
+    );
+}
+
+// Indexes the directory on a background thread, since a large mirror takes a
+// while the first time, then opens the search.
+fn index_directory(cursive: &mut Cursive, directory: PathBuf, controls_tx: Sender<ControlMessages>, index: SharedIndex) {
+    cursive.add_layer(Dialog::text(format!("Indexing {}...", directory.display())).title("Pattern directory"));
+    let cb_sink = cursive.cb_sink().clone();
+    std::thread::spawn(move || {
+        // The layer above has to come down however indexing ends, so a panic
+        // is reported like any other failure.
+        let result = std::panic::catch_unwind(|| PatternIndex::open(&directory))
+            .unwrap_or_else(|_| Err(std::io::Error::other("indexing stopped unexpectedly, see the log")));
+        let _ = cb_sink.send(Box::new(move |cursive: &mut Cursive| {
+            cursive.pop_layer();
+            match result {
+                Ok(new_index) => {
+                    tracing::info!("Indexed {} patterns in {:?}.", new_index.entries.len(), new_index.root);
+                    if let Ok(mut shared) = index.lock() {
+                        *shared = Some(new_index);
+                    }
+                    search_dialog(cursive, controls_tx, index);
+                }
+                Err(error) => {
+                    tracing::error!("Unable to index {:?}. {error}", directory);
+                    cursive.add_layer(Dialog::info(format!("Unable to index {}: {error}", directory.display())));
+                }
+            }
+        }));
+    });
+}
+
+fn search_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, index: SharedIndex) {
+    let query_index = index.clone();
+    let query = EditView::new()
+        .on_edit(move |cursive, text, _cursor| show_results(cursive, &query_index, text))
+        .with_name("Pattern query")
+        .min_width(40);
+
+    let select_index = index.clone();
+    let results = SelectView::<IndexEntry>::new()
+        .on_select(move |cursive, entry| show_entry(cursive, &select_index, entry))
+        .with_name("Pattern results")
+        .scrollable()
+        .min_size((40, 15));
+
+    let preview_pane = LinearLayout::vertical()
+        .child(TextView::new("").with_name("Search preview"))
+        .child(TextView::new("").with_name("Search description"))
+        .min_width(40);
+
+    let stamp_index = index.clone();
+    let directory_index = index.clone();
+    let directory_tx = controls_tx.clone();
+    cursive.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(TextView::new("Name words, author:, rule:, pop/period/width/height with < <= = >= >"))
+                .child(query)
+                .child(
+                    LinearLayout::horizontal()
+                        .child(Panel::new(results).title("Matches"))
+                        .child(Panel::new(preview_pane).title("Preview")),
+                ),
+        )
+        .title("Search patterns")
+        .button("Stamp", move |cursive| {
+            let chosen = cursive
+                .call_on_name("Pattern results", |results: &mut SelectView<IndexEntry>| results.selection())
+                .flatten();
+            let Some(chosen) = chosen else {
+                return;
+            };
+            let rle = stamp_index.lock().ok().and_then(|index| index.as_ref()?.load(&chosen));
+            match rle {
+                Some(rle) => {
+                    let pos = board_cursor(cursive);
+                    if let Err(error) = controls_tx.try_send(ControlMessages::Stamp(rle.pattern, pos)) {
+                        tracing::error!("Unable to send stamp message on controls sender channel. {:?}", error);
+                    }
+                    cursive.pop_layer();
+                }
+                None => cursive.add_layer(Dialog::info(format!("Unable to load {}.", chosen.path.display()))),
+            }
+        })
+        .button("Directory", move |cursive| {
+            cursive.pop_layer();
+            directory_dialog(cursive, directory_tx.clone(), directory_index.clone());
+        })
+        .dismiss_button("Close"),
+    );
+
+    show_results(cursive, &index, "");
+}
+
+fn show_results(cursive: &mut Cursive, index: &SharedIndex, text: &str) {
+    let query = match Query::parse(text) {
+        Ok(query) => query,
+        Err(error) => {
+            cursive.call_on_name("Search description", |view: &mut TextView| view.set_content(error.to_string()));
+            return;
+        }
+    };
+    let matches: Vec<IndexEntry> = match index.lock() {
+        Ok(index) => index
+            .as_ref()
+            .map(|index| index.search(&query).into_iter().take(MAX_RESULTS).cloned().collect())
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    cursive.call_on_name("Pattern results", |results: &mut SelectView<IndexEntry>| {
+        results.clear();
+        results.add_all(matches.into_iter().map(|entry| {
+            let period = entry.period.map_or_else(String::new, |period| format!(" p{period}"));
+            let label = format!("{} ({}x{}, {} cells{period})", entry.name, entry.width, entry.height, entry.population);
+            (label, entry)
+        }));
+    });
+}
+
+fn show_entry(cursive: &mut Cursive, index: &SharedIndex, entry: &IndexEntry) {
+    let rle = index.lock().ok().and_then(|index| index.as_ref()?.load(entry));
+    let (preview_text, description) = match rle {
+        Some(rle) => {
+            let mut description = describe(&rle);
+            if let Some(rule) = entry.rule.as_ref() {
+                description.push_str(&format!("\nRule {rule}"));
+            }
+            description.push_str(&format!("\n{}", entry.path.display()));
+            (preview(&rle.pattern), description)
+        }
+        None => (String::new(), format!("Unable to load {}.", entry.path.display())),
+    };
+    cursive.call_on_name("Search preview", |view: &mut TextView| view.set_content(preview_text));
+    cursive.call_on_name("Search description", |view: &mut TextView| view.set_content(description));
+}
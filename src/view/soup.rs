@@ -0,0 +1,80 @@
+use cursive::{
+    view::{Nameable, Resizable},
+    views::{Dialog, EditView, ListView, SelectView},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::simulation_controls::ControlMessages;
+use crate::state::soup::{parse_region, SoupParams, Symmetry, DEFAULT_DENSITY};
+
+// Asks for the parameters of a new random soup and resets the board with it.
+pub fn soup_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>) {
+    let symmetries = SelectView::new()
+        .popup()
+        .with_all(Symmetry::ALL.iter().map(|symmetry| (symmetry.to_string(), *symmetry)))
+        .with_name("Soup symmetry");
+
+    cursive.add_layer(
+        Dialog::around(
+            ListView::new()
+                .child(
+                    "Density (0-1)",
+                    EditView::new()
+                        .content(DEFAULT_DENSITY.to_string())
+                        .with_name("Soup density")
+                        .fixed_width(12),
+                )
+                .child("Seed (blank: random)", EditView::new().with_name("Soup seed").fixed_width(12))
+                .child(
+                    "Region (blank: board)",
+                    EditView::new().content("16x16").with_name("Soup region").fixed_width(12),
+                )
+                .child("Symmetry", symmetries),
+        )
+        .title("Random soup")
+        .button("Generate", move |cursive| match read_params(cursive) {
+            Ok(params) => {
+                if let Err(error) = controls_tx.try_send(ControlMessages::ResetSoup(params)) {
+                    tracing::error!("Unable to send soup message on controls sender channel. {:?}", error);
+                }
+                cursive.pop_layer();
+            }
+            Err(error) => cursive.add_layer(Dialog::info(error)),
+        })
+        .dismiss_button("Cancel"),
+    );
+}
+
+fn read_params(cursive: &mut Cursive) -> Result<SoupParams, String> {
+    let content = |cursive: &mut Cursive, name: &str| {
+        cursive
+            .call_on_name(name, |view: &mut EditView| view.get_content().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let density = content(cursive, "Soup density")
+        .parse::<f64>()
+        .ok()
+        .filter(|density| (0.0..=1.0).contains(density))
+        .ok_or("Density must be a number between 0 and 1.")?;
+    let seed = match content(cursive, "Soup seed") {
+        seed if seed.is_empty() => None,
+        seed => Some(seed.parse::<u64>().map_err(|_| "Seed must be a whole number.")?),
+    };
+    let region = match content(cursive, "Soup region") {
+        region if region.is_empty() => None,
+        region => Some(parse_region(&region)?),
+    };
+    let symmetry = cursive
+        .call_on_name("Soup symmetry", |view: &mut SelectView<Symmetry>| view.selection())
+        .flatten()
+        .map_or(Symmetry::C1, |symmetry| *symmetry);
+
+    Ok(SoupParams {
+        density,
+        seed,
+        region,
+        symmetry,
+    })
+}
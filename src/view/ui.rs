@@ -1,26 +1,30 @@
-use std::cell::RefCell;
-
 use cursive::{
-    Cursive, event::Event, theme::ColorStyle, view::{Nameable, Resizable}, views::{BoxedView, Button, Canvas, LinearLayout, PaddedView, Panel}
+    Cursive, theme::Color, utils::markup::StyledString, event::{Event, EventResult, Key, MouseEvent}, view::{Nameable, Resizable}, views::{BoxedView, Button, Canvas, Dialog, EditView, LinearLayout, PaddedView, Panel, TextView}
 };
 use tokio::sync::mpsc::Sender;
 use tokio::sync::watch::Receiver;
 use tracing::{debug, info};
 
-use crate::state::{
-    cell::CellState,
-    game::GameData,
-};
+use crate::state::{cell::CellState, game::GameData, inspect::Inspection, simulation_controls::ControlMessages};
+
+use super::board::BoardState;
+use super::census::census_dialog;
+use super::jump::{jump_dialog, sync_jump_progress};
+use super::library::pattern_browser;
+use super::search::{pattern_search, SharedIndex};
+use super::files::{load_dialog, save_dialog};
+use super::soup::soup_dialog;
+use super::watches::watches_dialog;
 
 const OFFSET_X: usize = 5;
 const OFFSET_Y: usize = 5;
+const TIMELINE_WIDTH: usize = 30;
 
-#[derive(Debug)]
-pub enum ControlMessages {
-    Reset,
-    Start,
-    Stop,
-    Step
+// State backing the timeline scrubber under the board.
+struct TimelineState {
+    model_rx: Receiver<GameData>,
+    controls_tx: Sender<ControlMessages>,
+    width: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -43,34 +47,12 @@ impl UserInterface {
             OFFSET_Y,
             OFFSET_Y,
             Panel::new(
-                Canvas::new(RefCell::new(model_rx.clone()))
-                    .with_required_size(|_state, _screen_size| {
-                        // TODO: Figure out a better way to get size
-                        cursive::Vec2::new(50, 30)
-                    })
-                    .with_draw(|state, printer| {
-                        let rx = state.borrow_mut();
-                        let board = rx.borrow();
-                        let previous_board = board.previous_generation.clone().into_iter().flatten();
-                        let next_board = board.cells.clone().into_iter().flatten();
-                        let zipped_boards = next_board.zip(previous_board);
-                        tracing::debug!("Drawing board.");
-                        for (new_cell, old_cell) in zipped_boards {
-                            if let (Some(inner), Some(old_inner)) = (new_cell.as_ref(), old_cell.as_ref()) {
-                                let char_to_print = match (&inner.state, &old_inner.state) {
-                                    (CellState::Alive, CellState::Alive) => '█',
-                                    (CellState::Alive, CellState::Dead) => '▓',
-                                    (CellState::Dead, CellState::Alive) => '▒',
-                                    (CellState::Dead, CellState::Dead) => ' ',
-                                };
-                                
-                                printer.print(
-                                    (inner.x(), inner.y()),
-                                    &char_to_print.to_string()
-                                )
-                            }
-                        }
-                    }),
+                Canvas::new(BoardState::new(model_rx.clone(), controls_tx.clone()))
+                    .with_required_size(BoardState::required_size)
+                    .with_take_focus(|_state, _direction| Ok(EventResult::Consumed(None)))
+                    .with_draw(BoardState::draw)
+                    .with_on_event(BoardState::on_event)
+                    .with_name("Board"),
             ),
         ));
 
@@ -111,32 +93,264 @@ impl UserInterface {
                     OFFSET_X,
                     OFFSET_Y,
                     OFFSET_Y,
-                    Button::new("Step", UserInterface::step_simulation(controls_tx.clone()))
-                        .with_name("Step")
+                    LinearLayout::vertical()
+                        .child(
+                            Button::new("Step", UserInterface::step_simulation(controls_tx.clone()))
+                                .with_name("Step")
+                                .fixed_width(10),
+                        )
+                        .child(
+                            LinearLayout::horizontal()
+                                .child(TextView::new("by "))
+                                .child(EditView::new().content("1").with_name("Step size").fixed_width(7)),
+                        ),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Jump", UserInterface::jump_callback(controls_tx.clone(), model_rx.clone()))
+                        .with_name("Jump")
+                        .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Step Back", UserInterface::step_back_callback(controls_tx.clone()))
+                        .with_name("Step Back")
+                        .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Patterns", UserInterface::pattern_library_callback(controls_tx.clone()))
+                        .with_name("Patterns")
+                        .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Search", UserInterface::pattern_search_callback(controls_tx.clone()))
+                        .with_name("Search")
                         .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Soup", UserInterface::soup_callback(controls_tx.clone()))
+                        .with_name("Soup")
+                        .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new("Census", UserInterface::census_callback(model_rx.clone()))
+                        .with_name("Census")
+                        .fixed_width(10),
+                ))
+                .child(PaddedView::lrtb(
+                    OFFSET_X,
+                    OFFSET_X,
+                    OFFSET_Y,
+                    OFFSET_Y,
+                    Button::new(
+                        "Watches",
+                        UserInterface::watches_callback(controls_tx.clone(), model_rx.clone()),
+                    )
+                    .with_name("Watches")
+                    .fixed_width(10),
                 )),
+        );
+        let timeline = BoxedView::boxed(PaddedView::lrtb(
+            OFFSET_X,
+            OFFSET_X,
+            0,
+            0,
+            Panel::new(UserInterface::timeline(model_rx.clone(), controls_tx.clone())).title("Timeline"),
+        ));
+        let status = PaddedView::lrtb(OFFSET_X, OFFSET_X, 0, 0, TextView::new("").with_name("Status"));
+        let palette = PaddedView::lrtb(OFFSET_X, OFFSET_X, 0, 0, TextView::new("").with_name("Palette"));
+        let inspector = PaddedView::lrtb(
+            OFFSET_X,
+            OFFSET_X,
+            0,
+            0,
+            Panel::new(TextView::new("").with_name("Inspector")).title("Inspector"),
+        );
+        let board_and_timeline = LinearLayout::vertical()
+            .child(canvas)
+            .child(timeline)
+            .child(status)
+            .child(palette)
+            .child(inspector);
+        let layout = BoxedView::boxed(LinearLayout::horizontal().child(board_and_timeline).child(controls));
 
 
-        );
-        let layout = BoxedView::boxed(LinearLayout::horizontal().child(canvas).child(controls));
+        let undo_tx = controls_tx.clone();
+        cursive_ref.add_global_callback(Event::CtrlChar('z'), move |_cursive| {
+            if let Err(error) = undo_tx.try_send(ControlMessages::Undo) {
+                tracing::error!("Error sending control message undo. {error}");
+            }
+        });
+        let redo_tx = controls_tx.clone();
+        cursive_ref.add_global_callback(Event::CtrlChar('y'), move |_cursive| {
+            if let Err(error) = redo_tx.try_send(ControlMessages::Redo) {
+                tracing::error!("Error sending control message redo. {error}");
+            }
+        });
 
+        let save_tx = controls_tx.clone();
+        cursive_ref.add_global_callback(Event::CtrlChar('s'), move |cursive| save_dialog(cursive, save_tx.clone()));
+        let load_tx = controls_tx.clone();
+        cursive_ref.add_global_callback(Event::CtrlChar('o'), move |cursive| load_dialog(cursive, load_tx.clone()));
 
         let receiver_cloned = model_rx.clone();
+        let progress_tx = controls_tx.clone();
         cursive_ref.set_on_pre_event(Event::Refresh, move |cursive: &mut Cursive| {
             let game_state = receiver_cloned.borrow();
             cursive.call_on_name("Start/Stop", |view: &mut Button| {
                 view.set_label( if game_state.running { "Stop" } else { "Start" });
             });
+            cursive.call_on_name("Status", |view: &mut TextView| {
+                let mut status = format!("Seed {}", game_state.seed);
+                if let Some(cycle) = game_state.cycle {
+                    let kind = if cycle.is_still_life() {
+                        "Still life".to_string()
+                    } else if cycle.is_moving() {
+                        format!("Spaceship, period {}", cycle.period)
+                    } else {
+                        format!("Period {}", cycle.period)
+                    };
+                    status.push_str(&format!(" | {kind} since gen {}", cycle.onset));
+                }
+                if let Some(jump) = game_state.jump {
+                    status.push_str(&format!(
+                        " | Jumping to gen {}: {:.0}%",
+                        jump.to,
+                        jump.progress(game_state.generation) * 100.0
+                    ));
+                }
+                if !game_state.triggered.is_empty() {
+                    let triggered: Vec<String> = game_state.triggered.iter().map(|watch| watch.to_string()).collect();
+                    status.push_str(&format!(" | Paused on {}", triggered.join(", ")));
+                }
+                if let Some(message) = game_state.status.as_ref() {
+                    status.push_str(&format!(" | {message}"));
+                }
+                view.set_content(status);
+            });
+            let (cursor, brush) = cursive
+                .call_on_name("Board", |board: &mut Canvas<BoardState>| {
+                    (board.state_mut().cursor, board.state_mut().brush(&game_state))
+                })
+                .unwrap_or((Default::default(), CellState::Alive));
+            cursive.call_on_name("Palette", |view: &mut TextView| {
+                view.set_content(UserInterface::palette(&game_state, brush));
+            });
+            cursive.call_on_name("Inspector", |view: &mut TextView| {
+                let inspection = Inspection::of(&game_state, cursor);
+                view.set_content(inspection.map(|inspection| inspection.to_string()).unwrap_or_default());
+            });
+            sync_jump_progress(cursive, &game_state, &progress_tx);
         });
 
         Self { root: layout }
     }
 
+    // The states of a rule file, each with its color, number key and name,
+    // marking the one being painted.
+    fn palette(game_data: &GameData, brush: CellState) -> StyledString {
+        let mut palette = StyledString::new();
+        for (index, (red, green, blue)) in game_data.colors.iter().enumerate().skip(1) {
+            let selected = index == brush.index() as usize;
+            palette.append_plain(if selected { "[" } else { " " });
+            palette.append_styled("█", Color::Rgb(*red, *green, *blue));
+            let name = game_data.state_names.get(index).map_or("", String::as_str);
+            palette.append_plain(format!(" {index} {name}").trim_end());
+            palette.append_plain(if selected { "] " } else { "  " });
+        }
+
+        palette
+    }
+
+    // A scrubber over the rewind history. The bar spans the stored
+    // generations; clicking or dragging on it, or using the arrow keys, moves
+    // the board along the timeline.
+    fn timeline(model_rx: Receiver<GameData>, controls_tx: Sender<ControlMessages>) -> Canvas<TimelineState> {
+        Canvas::new(TimelineState {
+            model_rx,
+            controls_tx,
+            width: TIMELINE_WIDTH,
+        })
+        .with_required_size(|_state, _constraint| cursive::Vec2::new(TIMELINE_WIDTH, 2))
+        .with_layout(|state, size| state.width = size.x.max(1))
+        .with_take_focus(|_state, _direction| Ok(EventResult::Consumed(None)))
+        .with_draw(|state, printer| {
+            let game_data = state.model_rx.borrow();
+            let width = state.width;
+            let marker = (game_data.history_position * (width - 1))
+                .checked_div(game_data.history_len)
+                .unwrap_or(width - 1);
+            let bar: String = (0..width)
+                .map(|column| match column {
+                    column if column == marker => '┃',
+                    column if column < marker => '━',
+                    _ => '─',
+                })
+                .collect();
+            printer.print((0, 0), &bar);
+            printer.print(
+                (0, 1),
+                &format!(
+                    "Gen {} ({}/{})",
+                    game_data.generation, game_data.history_position, game_data.history_len
+                ),
+            );
+        })
+        .with_on_event(|state, event| {
+            let (position, len) = {
+                let game_data = state.model_rx.borrow();
+                (game_data.history_position, game_data.history_len)
+            };
+            let message = match event {
+                Event::Key(Key::Left) => ControlMessages::StepBack,
+                Event::Key(Key::Right) => ControlMessages::SeekHistory(position + 1),
+                Event::Key(Key::Home) => ControlMessages::SeekHistory(0),
+                Event::Key(Key::End) => ControlMessages::SeekHistory(len),
+                Event::Mouse {
+                    offset,
+                    position: mouse_position,
+                    event: MouseEvent::Press(_) | MouseEvent::Hold(_),
+                } => {
+                    let column = mouse_position.saturating_sub(offset).x.min(state.width - 1);
+                    ControlMessages::SeekHistory(column * len / (state.width - 1).max(1))
+                }
+                _ => return EventResult::Ignored,
+            };
+            if let Err(error) = state.controls_tx.try_send(message) {
+                tracing::error!("Unable to send seek message on controls sender channel. {:?}", error);
+            }
+
+            EventResult::Consumed(None)
+        })
+    }
+
     fn start_button_callback(
         controls_tx: Sender<ControlMessages>,
         model_rx: Receiver<GameData>,
     ) -> Box<dyn 'static + Fn(&mut Cursive)> {
-        return {
+        {
             let cloned_tx = controls_tx.clone();
             let cloned_rx = model_rx.clone();
             Box::new(move |_s: &mut Cursive| {
@@ -158,13 +372,13 @@ impl UserInterface {
                     }
                 }
             })
-        };
+        }
     }
 
     fn print_board_state_callback(
         model_rx: Receiver<GameData>
     ) -> Box<dyn 'static + Fn(&mut Cursive)> {
-        return {
+        {
             let cloned_rx = model_rx.clone();
             Box::new(move |_s: &mut Cursive| {
                 tracing::info!("Print board state button pressed.");
@@ -177,12 +391,26 @@ impl UserInterface {
     fn step_simulation(
         controls_tx: Sender<ControlMessages>
     ) -> Box<dyn 'static + Fn(&mut Cursive)> {
-        return {
+        {
             let cloned_tx = controls_tx.clone();
 
-            Box::new(move |_s: &mut Cursive| {
+            Box::new(move |s: &mut Cursive| {
                 tracing::info!("Step simulation button pressed.");
-                let send_result = cloned_tx.try_send(ControlMessages::Step);
+                // Steps of more than one generation are jumps, which don't
+                // draw the generations in between.
+                let step_size = s
+                    .call_on_name("Step size", |view: &mut EditView| view.get_content())
+                    .and_then(|content| content.trim().parse::<u64>().ok())
+                    .filter(|step_size| *step_size > 0);
+                let message = match step_size {
+                    Some(1) => ControlMessages::Step,
+                    Some(step_size) => ControlMessages::Advance(step_size),
+                    None => {
+                        s.add_layer(Dialog::info("The step size must be a whole number above 0."));
+                        return;
+                    }
+                };
+                let send_result = cloned_tx.try_send(message);
                 if let Err(error) = send_result {
                     tracing::error!("Error sending control message step. {error}");
                 }
@@ -190,10 +418,84 @@ impl UserInterface {
         }
     }
 
+    fn step_back_callback(
+        controls_tx: Sender<ControlMessages>
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        let cloned_tx = controls_tx.clone();
+
+        Box::new(move |_s: &mut Cursive| {
+            tracing::info!("Step back button pressed.");
+            if let Err(error) = cloned_tx.try_send(ControlMessages::StepBack) {
+                tracing::error!("Error sending control message step back. {error}");
+            }
+        })
+    }
+
+    fn pattern_library_callback(
+        controls_tx: Sender<ControlMessages>
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Pattern library button pressed.");
+            pattern_browser(s, controls_tx.clone());
+        })
+    }
+
+    fn pattern_search_callback(
+        controls_tx: Sender<ControlMessages>
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        let index = SharedIndex::default();
+
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Pattern search button pressed.");
+            pattern_search(s, controls_tx.clone(), index.clone());
+        })
+    }
+
+    fn soup_callback(
+        controls_tx: Sender<ControlMessages>
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Soup button pressed.");
+            soup_dialog(s, controls_tx.clone());
+        })
+    }
+
+    fn census_callback(
+        model_rx: Receiver<GameData>
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Census button pressed.");
+            let game_data = model_rx.borrow().clone();
+            census_dialog(s, &game_data);
+        })
+    }
+
+    fn jump_callback(
+        controls_tx: Sender<ControlMessages>,
+        model_rx: Receiver<GameData>,
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Jump button pressed.");
+            let generation = model_rx.borrow().generation;
+            jump_dialog(s, controls_tx.clone(), generation);
+        })
+    }
+
+    fn watches_callback(
+        controls_tx: Sender<ControlMessages>,
+        model_rx: Receiver<GameData>,
+    ) -> Box<dyn 'static + Fn(&mut Cursive)> {
+        Box::new(move |s: &mut Cursive| {
+            tracing::info!("Watches button pressed.");
+            let game_data = model_rx.borrow().clone();
+            watches_dialog(s, controls_tx.clone(), &game_data);
+        })
+    }
+
     fn reset_button_callback(
         controls_tx: Sender<ControlMessages>,
     ) -> Box<dyn 'static + Fn(&mut Cursive)> {
-        return {
+        {
             let cloned_tx = controls_tx.clone();
             Box::new(move |_s: &mut Cursive| {
                 tracing::info!("Reset button pressed.");
@@ -225,6 +527,6 @@ impl UserInterface {
                     _s.set_user_data(UserInterfaceData { running: true });
                 }
             })
-        };
+        }
     }
 }
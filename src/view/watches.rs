@@ -0,0 +1,70 @@
+use cursive::{
+    view::{Nameable, Resizable, Scrollable},
+    views::{Canvas, Dialog, EditView, LinearLayout, SelectView, TextView},
+    Cursive,
+};
+use tokio::sync::mpsc::Sender;
+
+use crate::state::{game::GameData, simulation_controls::ControlMessages, watch::Watch};
+
+use super::board::BoardState;
+
+// Lists the conditions that pause the run and lets new ones be added. A
+// selection on the board is offered as the region to watch.
+pub fn watches_dialog(cursive: &mut Cursive, controls_tx: Sender<ControlMessages>, game_data: &GameData) {
+    let selection = cursive
+        .call_on_name("Board", |board: &mut Canvas<BoardState>| board.state_mut().selection())
+        .flatten();
+    let suggestion = selection.map_or("pop>100".to_string(), |selection| {
+        Watch::RegionChanged(selection).to_string()
+    });
+    let watches = SelectView::<Watch>::new()
+        .with_all(game_data.watches.iter().map(|watch| (watch.to_string(), *watch)))
+        .with_name("Watches");
+
+    let add_tx = controls_tx.clone();
+    cursive.add_layer(
+        Dialog::around(
+            LinearLayout::vertical()
+                .child(watches.scrollable().min_height(5).max_height(12))
+                .child(TextView::new(
+                    "Pause when: pop>N, pop<N, change X,Y X,Y, periodic, extinct or gen=N",
+                ))
+                .child(EditView::new().content(suggestion).with_name("New watch").min_width(40)),
+        )
+        .title("Watches")
+        .button("Add", move |cursive| {
+            let content = cursive
+                .call_on_name("New watch", |view: &mut EditView| view.get_content())
+                .unwrap_or_default();
+            match content.parse::<Watch>() {
+                Ok(watch) => {
+                    send(&add_tx, ControlMessages::AddWatch(watch));
+                    cursive.call_on_name("Watches", |view: &mut SelectView<Watch>| {
+                        if !view.iter().any(|(_, existing)| *existing == watch) {
+                            view.add_item(watch.to_string(), watch);
+                        }
+                    });
+                }
+                Err(error) => cursive.add_layer(Dialog::info(error)),
+            }
+        })
+        .button("Remove", move |cursive| {
+            cursive.call_on_name("Watches", |view: &mut SelectView<Watch>| {
+                if let Some(index) = view.selected_id() {
+                    if let Some((_, watch)) = view.get_item(index) {
+                        send(&controls_tx, ControlMessages::RemoveWatch(*watch));
+                    }
+                    view.remove_item(index);
+                }
+            });
+        })
+        .dismiss_button("Close"),
+    );
+}
+
+fn send(controls_tx: &Sender<ControlMessages>, message: ControlMessages) {
+    if let Err(error) = controls_tx.try_send(message) {
+        tracing::error!("Unable to send watch message on controls sender channel. {:?}", error);
+    }
+}